@@ -0,0 +1,115 @@
+use std::{fmt::Write, sync::Arc};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use swc_core::{
+    common::{FileName, SourceMap, Span, Spanned},
+    ecma::{
+        ast::{EsVersion, Expr, Stmt},
+        parser::{parse_file_as_program, Syntax},
+        visit::{Visit, VisitWith},
+    },
+};
+use swc_coverage_instrument::{
+    get_range_from_span, get_range_from_span_uncached, instrument_sync, InstrumentOptions,
+    LookupCache,
+};
+
+/// Creates a large input mixing the node types instrumented most commonly:
+/// statements, functions, conditionals and logical expressions.
+fn create_fixture(count: usize) -> String {
+    (0..count).fold(String::new(), |mut code, i| {
+        write!(
+            code,
+            r#"function fn{i}(a, b = {i}) {{
+  var value = a && b || {i};
+  if (value > {i}) {{
+    return value ? a : b;
+  }} else if (a ?? b) {{
+    switch (a) {{
+      case {i}:
+        break;
+      default:
+        value++;
+    }}
+  }}
+  const arrow = (x) => x + value;
+  return arrow(a);
+}}
+"#
+        )
+        .expect("Should write fixture");
+        code
+    })
+}
+
+/// Collects the spans of the statements and expressions in visiting order,
+/// the nodes instrumentation looks up the ranges of.
+#[derive(Default)]
+struct SpanCollector(Vec<Span>);
+
+impl Visit for SpanCollector {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        self.0.push(stmt.span());
+        stmt.visit_children_with(self);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.0.push(expr.span());
+        expr.visit_children_with(self);
+    }
+}
+
+fn bench_instrument(c: &mut Criterion) {
+    let code = create_fixture(2000);
+
+    c.bench_function("instrument large file", |b| {
+        b.iter(|| {
+            instrument_sync(black_box(&code), "bench.js", InstrumentOptions::default())
+                .expect("Should instrument")
+        })
+    });
+}
+
+fn bench_lookup_range(c: &mut Criterion) {
+    let code = create_fixture(2000);
+
+    let source_map: Arc<SourceMap> = Default::default();
+    let fm = source_map.new_source_file(FileName::Anon.into(), code);
+    let program = parse_file_as_program(
+        &fm,
+        Syntax::Es(Default::default()),
+        EsVersion::latest(),
+        None,
+        &mut vec![],
+    )
+    .expect("Should able to parse");
+
+    let mut spans = SpanCollector::default();
+    program.visit_with(&mut spans);
+
+    let mut group = c.benchmark_group("lookup ranges");
+
+    // Baseline: every position is looked up from the source map.
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            for span in &spans.0 {
+                black_box(get_range_from_span_uncached(&source_map, span));
+            }
+        })
+    });
+
+    // Cache is created per file, same as the instrumentation visitor.
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            let cache = LookupCache::default();
+            for span in &spans.0 {
+                black_box(get_range_from_span(&source_map, &cache, span));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_instrument, bench_lookup_range);
+criterion_main!(benches);
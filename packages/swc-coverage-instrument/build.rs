@@ -1,17 +1,9 @@
-use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
-};
-
 /// Create compile-time constant values for the coverage schema hash & coverage lib version hash (magic-value)
 fn main() {
     let magic_key = "_coverageSchema";
-    let mut hasher = DefaultHasher::new();
-    let name = std::env::var("CARGO_PKG_NAME").unwrap();
-    // Use major as schema version, changing schema means major breaking anyway.
-    let version = std::env::var("CARGO_PKG_VERSION_MAJOR").unwrap();
-    format!("{}@{}", name, version).hash(&mut hasher);
-    let magic_value = hasher.finish().to_string();
+    // Same value as istanbul-lib-instrument emits: sha1 of `istanbul-lib-instrument@4`, where `4` is
+    // its coverage schema version. Update along with istanbul when it bumps the schema version.
+    let magic_value = "1a1c01bbd47fc00a2c39e90264f33305004495a9";
 
     let out_dir = std::env::var_os("OUT_DIR").unwrap();
     let path = std::path::Path::new(&out_dir).join("constants.rs");
@@ -82,11 +82,50 @@ pub static IDENT_B: Lazy<Ident> = Lazy::new(|| Ident {
     ..Ident::dummy()
 });
 
+/// Property idents for the statement / function / branch counters.
+/// Defaults to `s` / `f` / `b`, can be overridden via `InstrumentOptions::counter_idents`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CounterIdents {
+    pub s: Ident,
+    pub f: Ident,
+    pub b: Ident,
+}
+
+impl CounterIdents {
+    pub fn from_options(options: &crate::InstrumentCounterIdentOptions) -> CounterIdents {
+        let create_ident = |sym: &str| Ident {
+            sym: sym.into(),
+            ..Ident::dummy()
+        };
+
+        CounterIdents {
+            s: create_ident(&options.statement),
+            f: create_ident(&options.function),
+            b: create_ident(&options.branch),
+        }
+    }
+}
+
+impl Default for CounterIdents {
+    fn default() -> Self {
+        CounterIdents {
+            s: IDENT_S.clone(),
+            f: IDENT_F.clone(),
+            b: IDENT_B.clone(),
+        }
+    }
+}
+
 pub static IDENT_BT: Lazy<Ident> = Lazy::new(|| Ident {
     sym: "bT".into(),
     ..Ident::dummy()
 });
 
+pub static IDENT_L: Lazy<Ident> = Lazy::new(|| Ident {
+    sym: "l".into(),
+    ..Ident::dummy()
+});
+
 pub static IDENT_COVERAGE_MAGIC_KEY: Lazy<Ident> = Lazy::new(|| Ident {
     sym: crate::COVERAGE_MAGIC_KEY.into(),
     ..Ident::dummy()
@@ -1,12 +1,13 @@
-use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
+use istanbul_oxide::{
+    Branch, BranchHitMap, BranchMap, FileCoverage, FunctionMap, LineHitMap, Range, SourceMap,
+    StatementMap,
 };
-
-use istanbul_oxide::{Branch, FileCoverage, Range};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
 use swc_core::{
     common::{util::take::Take, DUMMY_SP},
     ecma::ast::*,
+    quote,
 };
 
 use crate::constants::idents::*;
@@ -168,7 +169,91 @@ fn create_branch_prop(key: &str, value: &Branch) -> PropOrSpread {
     )
 }
 
-pub fn create_coverage_data_object(coverage_data: &FileCoverage) -> (String, Expr) {
+/// Creates `l` getter returns the hits per line, keyed by line number.
+/// The hits are derived from the statement hits whenever it is read (i.e serialized by the reporter),
+/// same as istanbul's `FileCoverage.getLineCoverage()`: the max hits of the statements start at the line.
+/// Lines without any statement start are not included.
+fn create_line_hits_getter_prop(counter_idents: &CounterIdents) -> PropOrSpread {
+    let stmts = vec![
+        quote!(
+            "var s = this[$s], l = {};" as Stmt,
+            s: Expr = create_str_lit_expr(&counter_idents.s.sym)
+        ),
+        quote!(
+            r#"
+for (var id in this.statementMap) {
+  var line = this.statementMap[id].start.line;
+  if (!(line in l) || l[line] < s[id]) {
+    l[line] = s[id];
+  }
+}
+"# as Stmt
+        ),
+        quote!("return l;" as Stmt),
+    ];
+
+    PropOrSpread::Prop(Box::new(Prop::Getter(GetterProp {
+        span: DUMMY_SP,
+        key: PropName::Ident(IDENT_L.clone().into()),
+        type_ann: None,
+        body: Some(BlockStmt {
+            span: DUMMY_SP,
+            stmts,
+            ..BlockStmt::dummy()
+        }),
+    })))
+}
+
+/// Coverage data in the shape of istanbul's `FileCoverage.toJSON()`, which the hash is created from.
+/// Unlike `FileCoverage`, there is no `all` flag as istanbul-lib-instrument does not emit it.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IstanbulFileCoverage<'a> {
+    path: &'a str,
+    statement_map: &'a StatementMap,
+    fn_map: &'a FunctionMap,
+    branch_map: &'a BranchMap,
+    s: &'a LineHitMap,
+    f: &'a LineHitMap,
+    b: &'a BranchHitMap,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b_t: Option<&'a BranchHitMap>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input_source_map: Option<&'a SourceMap>,
+    // istanbul-lib-instrument assigns the schema key before creating the hash.
+    #[serde(rename = "_coverageSchema")]
+    coverage_schema: &'a str,
+}
+
+/// Creates the per-file `hash` of the coverage data. The injected coverage fn compares it
+/// against the registered coverage object to reset stale data when the file is re-instrumented.
+///
+/// Same as istanbul-lib-instrument, it is a sha1 hex digest of the JSON serialized coverage data.
+fn create_coverage_hash(coverage_data: &FileCoverage) -> String {
+    let coverage = IstanbulFileCoverage {
+        path: &coverage_data.path,
+        statement_map: &coverage_data.statement_map,
+        fn_map: &coverage_data.fn_map,
+        branch_map: &coverage_data.branch_map,
+        s: &coverage_data.s,
+        f: &coverage_data.f,
+        b: &coverage_data.b,
+        b_t: coverage_data.b_t.as_ref(),
+        input_source_map: coverage_data.input_source_map.as_ref(),
+        coverage_schema: COVERAGE_MAGIC_VALUE,
+    };
+    let coverage_str =
+        serde_json::to_string(&coverage).expect("Should able to serialize coverage data");
+
+    format!("{:x}", Sha1::digest(coverage_str.as_bytes()))
+}
+
+pub fn create_coverage_data_object(
+    coverage_data: &FileCoverage,
+    counter_idents: &CounterIdents,
+    typed_array_fn_counters: bool,
+    track_lines: bool,
+) -> (String, Expr) {
     // Afaik there's no built-in way to iterate over struct properties via keys.
     let mut props = vec![];
 
@@ -245,7 +330,7 @@ pub fn create_coverage_data_object(coverage_data: &FileCoverage) -> (String, Exp
         .collect();
 
     let s_prop = create_ident_key_value_prop(
-        &IDENT_S,
+        &counter_idents.s,
         Expr::Object(ObjectLit {
             span: DUMMY_SP,
             props: s_prop_values,
@@ -260,13 +345,24 @@ pub fn create_coverage_data_object(coverage_data: &FileCoverage) -> (String, Exp
             create_str_key_value_prop(&key.to_string(), create_num_lit_expr(*value))
         })
         .collect();
-    let f_prop = create_ident_key_value_prop(
-        &IDENT_F,
-        Expr::Object(ObjectLit {
-            span: DUMMY_SP,
-            props: f_prop_values,
-        }),
-    );
+    let f_object = Expr::Object(ObjectLit {
+        span: DUMMY_SP,
+        props: f_prop_values,
+    });
+    let f_prop = if typed_array_fn_counters {
+        // typeof Uint32Array === "function" ? new Uint32Array($len) : { ... }
+        create_ident_key_value_prop(
+            &counter_idents.f,
+            quote!(
+                "typeof Uint32Array === \"function\" ? new Uint32Array($len) : $f_object"
+                    as Expr,
+                len: Expr = create_num_lit_expr(coverage_data.f.len() as u32),
+                f_object: Expr = f_object
+            ),
+        )
+    } else {
+        create_ident_key_value_prop(&counter_idents.f, f_object)
+    };
     props.push(f_prop);
 
     let b_prop_values = coverage_data
@@ -277,7 +373,7 @@ pub fn create_coverage_data_object(coverage_data: &FileCoverage) -> (String, Exp
         })
         .collect();
     let b_prop = create_ident_key_value_prop(
-        &IDENT_B,
+        &counter_idents.b,
         Expr::Object(ObjectLit {
             span: DUMMY_SP,
             props: b_prop_values,
@@ -302,6 +398,11 @@ pub fn create_coverage_data_object(coverage_data: &FileCoverage) -> (String, Exp
         props.push(b_t_prop);
     }
 
+    // assign coverage['l']
+    if track_lines {
+        props.push(create_line_hits_getter_prop(counter_idents));
+    }
+
     // assign coverage['inputSourceMap']
     if let Some(input_source_map) = &coverage_data.input_source_map {
         let mut source_map_props = vec![];
@@ -417,18 +518,14 @@ pub fn create_coverage_data_object(coverage_data: &FileCoverage) -> (String, Exp
     }
 
     // assign coverage['_coverageSchema']
+    // Value is istanbul-lib-instrument's own (see build.rs), so reporters validating the schema accept the object.
     let coverage_schema_prop = create_ident_key_value_prop(
         &IDENT_COVERAGE_MAGIC_KEY,
         create_str_lit_expr(&COVERAGE_MAGIC_VALUE),
     );
     props.push(coverage_schema_prop);
 
-    // Original code creates hash against raw coverage object. In here uses str-serialized object instead.
-    let coverage_str =
-        serde_json::to_string(coverage_data).expect("Should able to serialize coverage data");
-    let mut hasher = DefaultHasher::new();
-    coverage_str.hash(&mut hasher);
-    let hash = hasher.finish().to_string();
+    let hash = create_coverage_hash(coverage_data);
 
     // assign coverage['hash']
     props.push(create_ident_key_value_prop(
@@ -475,7 +572,8 @@ mod tests {
     fn should_create_empty() {
         let file_path = "anon";
         let coverage_data = FileCoverage::empty(file_path.to_string(), false);
-        let (_hash, coverage_data_expr) = create_coverage_data_object(&coverage_data);
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(&coverage_data, &Default::default(), false, false);
 
         let mut expected = quote!(
             r#"
@@ -488,8 +586,124 @@ mod tests {
             s: {},
             f: {},
             b: {},
-            _coverageSchema: "11020577277169172593",
-            hash: "2749072808032864045"
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "a1c0ef7e2c43ee37dfa19c89a5306f62fd93efae"
+        }
+        "# as Expr
+        );
+        adjust_expected_ast_path_raw(&mut expected, 1, file_path);
+
+        assert_eq!(expected, coverage_data_expr);
+    }
+
+    #[test]
+    fn should_create_stable_hash() {
+        let coverage_data = FileCoverage::empty("anon".to_string(), false);
+        let (hash, _) =
+            create_coverage_data_object(&coverage_data, &Default::default(), false, false);
+        // Same digest istanbul-lib-instrument creates for the empty coverage of `anon`, i.e sha1 of
+        // `{"path":"anon",...,"b":{},"_coverageSchema":"1a1c01bbd47fc00a2c39e90264f33305004495a9"}`.
+        assert_eq!(hash, "a1c0ef7e2c43ee37dfa19c89a5306f62fd93efae");
+        assert_eq!(hash, create_coverage_hash(&coverage_data));
+
+        let mut coverage_data = SourceCoverage::new("anon".to_string(), false);
+        coverage_data.new_statement(&Range::new(1, 0, 1, 10));
+        let (changed_hash, _) =
+            create_coverage_data_object(coverage_data.as_ref(), &Default::default(), false, false);
+        assert_ne!(changed_hash, hash);
+    }
+
+    #[test]
+    fn should_create_empty_with_counter_idents() {
+        let file_path = "anon";
+        let coverage_data = FileCoverage::empty(file_path.to_string(), false);
+        let counter_idents = CounterIdents::from_options(&crate::InstrumentCounterIdentOptions {
+            statement: "st".to_string(),
+            function: "fn".to_string(),
+            branch: "br".to_string(),
+        });
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(&coverage_data, &counter_idents, false, false);
+
+        let mut expected = quote!(
+            r#"
+        {
+            all: false,
+            path: "anon",
+            statementMap: {},
+            fnMap: {},
+            branchMap: {},
+            st: {},
+            fn: {},
+            br: {},
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "a1c0ef7e2c43ee37dfa19c89a5306f62fd93efae"
+        }
+        "# as Expr
+        );
+        adjust_expected_ast_path_raw(&mut expected, 1, file_path);
+
+        assert_eq!(expected, coverage_data_expr);
+    }
+
+    #[test]
+    fn should_create_empty_with_typed_array_fn_counters() {
+        let file_path = "anon";
+        let coverage_data = FileCoverage::empty(file_path.to_string(), false);
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(&coverage_data, &Default::default(), true, false);
+
+        let mut expected = quote!(
+            r#"
+        {
+            all: false,
+            path: "anon",
+            statementMap: {},
+            fnMap: {},
+            branchMap: {},
+            s: {},
+            f: typeof Uint32Array === "function" ? new Uint32Array(0) : {},
+            b: {},
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "a1c0ef7e2c43ee37dfa19c89a5306f62fd93efae"
+        }
+        "# as Expr
+        );
+        adjust_expected_ast_path_raw(&mut expected, 1, file_path);
+
+        assert_eq!(expected, coverage_data_expr);
+    }
+
+    #[test]
+    fn should_create_empty_with_line_hits() {
+        let file_path = "anon";
+        let coverage_data = FileCoverage::empty(file_path.to_string(), false);
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(&coverage_data, &Default::default(), false, true);
+
+        let mut expected = quote!(
+            r#"
+        {
+            all: false,
+            path: "anon",
+            statementMap: {},
+            fnMap: {},
+            branchMap: {},
+            s: {},
+            f: {},
+            b: {},
+            get l() {
+                var s = this["s"], l = {};
+                for (var id in this.statementMap) {
+                  var line = this.statementMap[id].start.line;
+                  if (!(line in l) || l[line] < s[id]) {
+                    l[line] = s[id];
+                  }
+                }
+                return l;
+            },
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "a1c0ef7e2c43ee37dfa19c89a5306f62fd93efae"
         }
         "# as Expr
         );
@@ -503,7 +717,8 @@ mod tests {
         let file_path = "anon";
         let mut coverage_data = FileCoverage::empty(file_path.to_string(), false);
         coverage_data.all = true;
-        let (_hash, coverage_data_expr) = create_coverage_data_object(&coverage_data);
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(&coverage_data, &Default::default(), false, false);
 
         let mut expected = quote!(
             r#"
@@ -516,8 +731,8 @@ mod tests {
             s: {},
             f: {},
             b: {},
-            _coverageSchema: "11020577277169172593",
-            hash: "9996448737459597674"
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "a1c0ef7e2c43ee37dfa19c89a5306f62fd93efae"
         }
         "# as Expr
         );
@@ -530,7 +745,8 @@ mod tests {
     fn should_create_empty_report_logic() {
         let file_path = "/test/src/file.js";
         let coverage_data = FileCoverage::empty(file_path.to_string(), true);
-        let (_hash, coverage_data_expr) = create_coverage_data_object(&coverage_data);
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(&coverage_data, &Default::default(), false, false);
 
         let mut expected = quote!(
             r#"
@@ -544,8 +760,8 @@ mod tests {
             f: {},
             b: {},
             bT: {},
-            _coverageSchema: "11020577277169172593",
-            hash: "5324777076056671972"
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "04940b9186bb7a947664a485ad339dc4ad5efc7f"
         }
         "# as Expr
         );
@@ -562,7 +778,8 @@ mod tests {
         let dummy_range = Range::new(2, 3, 5, 2);
         coverage_data.new_statement(&dummy_range);
 
-        let (_hash, coverage_data_expr) = create_coverage_data_object(coverage_data.as_ref());
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(coverage_data.as_ref(), &Default::default(), false, false);
 
         let mut expected = quote!(
             r#"
@@ -588,8 +805,8 @@ mod tests {
             },
             f: {},
             b: {},
-            _coverageSchema: "11020577277169172593",
-            hash: "14358638674647738158"
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "78605c26929309f7bfc3dab2e3c3b5e33577dc49"
         }
         "# as Expr
         );
@@ -635,14 +852,15 @@ mod tests {
             },
             f: {},
             b: {},
-            _coverageSchema: "11020577277169172593",
-            hash: "8495704048820686839"
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "38e63464ea698ecdcffac473f23046f6b71a9b5b"
         }
         "# as Expr
         );
         adjust_expected_ast_path_raw(&mut expected, 1, file_path);
 
-        let (_hash, coverage_data_expr) = create_coverage_data_object(coverage_data.as_ref());
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(coverage_data.as_ref(), &Default::default(), false, false);
         assert_eq!(expected, coverage_data_expr);
     }
 
@@ -659,7 +877,8 @@ mod tests {
             &dummy_range,
         );
 
-        let (_hash, coverage_data_expr) = create_coverage_data_object(coverage_data.as_ref());
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(coverage_data.as_ref(), &Default::default(), false, false);
 
         let mut expected = quote!(
             r#"
@@ -699,8 +918,8 @@ mod tests {
                 "0": 0,
             },
             b: {},
-            _coverageSchema: "11020577277169172593",
-            hash: "12684468276621003816"
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "b2606ee6aab34b7c40e1e0b25c0abe4d814ca226"
         }
         "# as Expr
         );
@@ -775,14 +994,15 @@ mod tests {
                 "1": 0
             },
             b: {},
-            _coverageSchema: "11020577277169172593",
-            hash: "8413193639409683826"
+            _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+            hash: "6e1ce226967ce99519b0af47ae7a3e6ffec7de4d"
         }
         "# as Expr
         );
         adjust_expected_ast_path_raw(&mut expected, 1, file_path);
 
-        let (_hash, coverage_data_expr) = create_coverage_data_object(coverage_data.as_ref());
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(coverage_data.as_ref(), &Default::default(), false, false);
         assert_eq!(expected, coverage_data_expr);
     }
 
@@ -794,7 +1014,8 @@ mod tests {
         let dummy_range = Range::new(2, 3, 5, 2);
         coverage_data.new_branch(BranchType::Switch, &dummy_range, false);
 
-        let (_hash, coverage_data_expr) = create_coverage_data_object(coverage_data.as_ref());
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(coverage_data.as_ref(), &Default::default(), false, false);
 
         let mut expected = quote!(
             r#"
@@ -813,8 +1034,8 @@ mod tests {
           s: {},
           f: {},
           b: { "0": [] },
-          _coverageSchema: "11020577277169172593",
-          hash: "16290170317654300968"
+          _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+          hash: "3cbbca427ab9fa65ad407da69cb988ef9976350b"
         }
         "# as Expr
         );
@@ -825,7 +1046,8 @@ mod tests {
         let dummy_range = Range::new(6, 4, 2, 8);
         coverage_data.new_branch(BranchType::BinaryExpr, &dummy_range, true);
 
-        let (_hash, coverage_data_expr) = create_coverage_data_object(coverage_data.as_ref());
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(coverage_data.as_ref(), &Default::default(), false, false);
 
         let mut expected = quote!(
             r#"
@@ -850,8 +1072,8 @@ mod tests {
           f: {},
           b: { "0": [], "1": [] },
           bT: { "1": [] },
-          _coverageSchema: "11020577277169172593",
-          hash: "394046461779423801"
+          _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+          hash: "b5422e5ab89d85536fd15809495698ea03890542"
         }
         "# as Expr
         );
@@ -870,7 +1092,8 @@ mod tests {
         let name = coverage_data.new_branch(BranchType::Switch, &dummy_range, false);
         coverage_data.add_branch_path(name, &location_range);
 
-        let (_hash, coverage_data_expr) = create_coverage_data_object(coverage_data.as_ref());
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(coverage_data.as_ref(), &Default::default(), false, false);
 
         let mut expected = quote!(
             r#"
@@ -890,8 +1113,8 @@ mod tests {
           s: {},
           f: {},
           b: { "0": [0] },
-          _coverageSchema: "11020577277169172593",
-          hash: "1206056395566328244"
+          _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+          hash: "812ea771d8bd3c6aebf030498cef93e265a1ad7a"
         }
         "# as Expr
         );
@@ -903,7 +1126,8 @@ mod tests {
         let name = coverage_data.new_branch(BranchType::BinaryExpr, &dummy_range, true);
         coverage_data.add_branch_path(name, &location_range);
 
-        let (_hash, coverage_data_expr) = create_coverage_data_object(coverage_data.as_ref());
+        let (_hash, coverage_data_expr) =
+            create_coverage_data_object(coverage_data.as_ref(), &Default::default(), false, false);
 
         let mut expected = quote!(
             r#"
@@ -930,8 +1154,8 @@ mod tests {
           f: {},
           b: { "0": [0], "1": [0] },
           bT: { "1": [0] },
-          _coverageSchema: "11020577277169172593",
-          hash: "5849348874565150566"
+          _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305004495a9",
+          hash: "ded8b99b1ca5765e8c8202bc0d6e18e9b75027d5"
         }
         "# as Expr
         );
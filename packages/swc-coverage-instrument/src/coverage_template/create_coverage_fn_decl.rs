@@ -4,7 +4,6 @@ use std::{
 };
 
 use istanbul_oxide::FileCoverage;
-use once_cell::sync::OnceCell;
 use swc_core::{
     common::{
         comments::{Comment, CommentKind, Comments},
@@ -17,43 +16,52 @@ use swc_core::{
 
 use crate::constants::idents::*;
 
-use crate::{create_assignment_stmt, create_coverage_data_object};
+use crate::{
+    create_assignment_stmt, create_coverage_data_object, encode_coverage_data, InstrumentOptions,
+    MetadataEncoding,
+};
+
+/// Default prefix of the trailing comment carrying serialized coverage data.
+pub const COVERAGE_DATA_JSON_COMMENT_PREFIX: &str = "__coverage_data_json_comment__::";
 
-pub static COVERAGE_FN_IDENT: OnceCell<Ident> = OnceCell::new();
-/// temporal ident being used for b_t true counter
-pub static COVERAGE_FN_TRUE_TEMP_IDENT: OnceCell<Ident> = OnceCell::new();
+/// Key of the registry on the global coverage object `flush_on_exit` assigns back when process exits.
+const COVERAGE_FLUSH_KEY: &str = "__coverage_flush__";
 
-/// Create a unique ident for the injected coverage counter fn,
-/// Stores it into a global scope.
+/// Create a unique ident for the injected coverage counter fn, along with
+/// the temporal ident being used for b_t true counter.
 ///
-/// Do not use static value directly - create_instrumentation_visitor macro
-/// should inject this into a struct accordingly.
-pub fn create_coverage_fn_ident(value: &str) {
+/// Idents are derived from the given value (file path) per call, so instrumenting
+/// multiple files in a same process gets a distinct counter fn for each file.
+pub fn create_coverage_fn_ident(value: &str) -> (Ident, Ident) {
     let mut s = DefaultHasher::new();
     value.hash(&mut s);
     let var_name_hash = format!("cov_{}", s.finish());
 
-    COVERAGE_FN_IDENT
-        .get_or_init(|| Ident::new(var_name_hash.clone().into(), DUMMY_SP, Default::default()));
-    COVERAGE_FN_TRUE_TEMP_IDENT.get_or_init(|| {
+    (
+        Ident::new(var_name_hash.clone().into(), DUMMY_SP, Default::default()),
         Ident::new(
             format!("{}_temp", var_name_hash).into(),
             DUMMY_SP,
             Default::default(),
-        )
-    });
+        ),
+    )
 }
 
 /// Creates a function declaration for actual coverage collection.
+///
+/// The function always registers the coverage data into `global[coverageVariable][path]`, same as
+/// istanbul's `coverageData` registration, so it is never reachable only through the local `cov_*` fn.
+/// When it happens is controlled by `initializeCoverage` (explicit call on load, or the first counter).
 pub fn create_coverage_fn_decl<C: Clone + Comments>(
-    coverage_variable: &str,
     coverage_template: Stmt,
     cov_fn_ident: &Ident,
     file_path: &str,
     coverage_data: &FileCoverage,
     comments: &C,
-    attach_debug_comment: bool,
+    counter_idents: &CounterIdents,
+    instrument_options: &InstrumentOptions,
 ) -> Stmt {
+    let coverage_variable = instrument_options.namespaced_coverage_variable();
     // Actual fn body statements will be injected
     let mut stmts = vec![];
 
@@ -67,14 +75,19 @@ pub fn create_coverage_fn_decl<C: Clone + Comments>(
     );
     stmts.push(path_stmt);
 
-    let (hash, coverage_data_object) = create_coverage_data_object(coverage_data);
+    let (hash, coverage_data_object) = create_coverage_data_object(
+        coverage_data,
+        counter_idents,
+        instrument_options.typed_array_fn_counters,
+        instrument_options.track_lines,
+    );
 
     // var hash = $HASH;
     let hash_stmt =
         create_assignment_stmt(&IDENT_HASH, Expr::Lit(Lit::Str(Str::from(hash.clone()))));
     stmts.push(hash_stmt);
 
-    // var global = new Function("return $global_coverage_scope")();
+    // var global = typeof globalThis !== "undefined" ? globalThis : ... : new Function("return this")();
     stmts.push(coverage_template);
 
     // var gcv = ${coverage_variable};
@@ -120,10 +133,77 @@ if (!$coverage[$path] || $coverage[$path].$hash !== $hash) {
         path = IDENT_PATH.clone()
     ));
 
-    //
-    //COVERAGE_FUNCTION = function () {
-    //   return actualCoverage;
-    //}
+    stmts.push(create_coverage_fn_reassign_stmt(
+        cov_fn_ident,
+        &actual_coverage_ident,
+    ));
+
+    // Register coverage data again when process exits, in case the global coverage variable
+    // has been reset before the reporter reads it.
+    // Files share a single exit hook: the first file registers it with a registry of the actual
+    // coverage objects per path, stored as a non-enumerable prop of the global coverage object
+    // (reporters iterate its keys as paths). Rest of the files add theirs into the registry.
+    if instrument_options.flush_on_exit {
+        stmts.push(quote!(
+            r#"
+if (typeof process === "object" && process !== null && typeof process.once === "function") {
+  var flush = $coverage[$flush_key];
+  if (!flush) {
+    flush = {};
+    Object.defineProperty($coverage, $flush_key, { value: flush });
+    process.once("exit", function () {
+      var current = $global[$gcv] || ($global[$gcv] = {});
+      for (var file in flush) {
+        current[file] = flush[file];
+      }
+    });
+  }
+  flush[$path] = $actual_coverage;
+}
+"# as Stmt,
+            coverage = coverage_ident.clone(),
+            flush_key: Expr = Expr::Lit(Lit::Str(Str::from(COVERAGE_FLUSH_KEY))),
+            global = IDENT_GLOBAL.clone(),
+            gcv = IDENT_GCV.clone(),
+            path = IDENT_PATH.clone(),
+            actual_coverage = actual_coverage_ident.clone()
+        ));
+    }
+
+    if let Some(debug_comment_prefix) = instrument_options.debug_comment_prefix() {
+        add_coverage_data_comment(
+            comments,
+            debug_comment_prefix,
+            coverage_data,
+            instrument_options.metadata_encoding,
+        );
+    }
+
+    let ret = ReturnStmt {
+        span: DUMMY_SP,
+        arg: Some(Box::new(Expr::Ident(actual_coverage_ident.clone()))),
+    };
+    stmts.push(Stmt::Return(ret));
+
+    // moduleitem for fn decl includes body defined above
+    Stmt::Decl(Decl::Fn(FnDecl {
+        ident: cov_fn_ident.clone(),
+        declare: false,
+        function: Box::new(Function {
+            body: Some(BlockStmt {
+                span: DUMMY_SP,
+                stmts,
+                ..BlockStmt::dummy()
+            }),
+            ..Function::dummy()
+        }),
+    }))
+}
+
+/// COVERAGE_FUNCTION = function () {
+///   return actualCoverage;
+/// }
+fn create_coverage_fn_reassign_stmt(cov_fn_ident: &Ident, actual_coverage_ident: &Ident) -> Stmt {
     // TODO: need to add @ts-ignore leading comment
     let coverage_fn_assign_expr = Expr::Assign(AssignExpr {
         left: BindingIdent::from(cov_fn_ident.clone()).into(),
@@ -144,39 +224,103 @@ if (!$coverage[$path] || $coverage[$path].$hash !== $hash) {
         ..AssignExpr::dummy()
     });
 
-    stmts.push(Stmt::Block(BlockStmt {
+    Stmt::Block(BlockStmt {
         stmts: vec![Stmt::Expr(ExprStmt {
             span: DUMMY_SP,
             expr: Box::new(coverage_fn_assign_expr),
         })],
         ..BlockStmt::dummy()
-    }));
+    })
+}
 
-    let ret = ReturnStmt {
-        span: DUMMY_SP,
-        arg: Some(Box::new(Expr::Ident(actual_coverage_ident.clone()))),
-    };
+/// Append coverage data as serialized comments at the bottom of transformed code.
+/// Currently plugin does not have way to pass any other data to the host except transformed program.
+/// This attaches arbitary data to the transformed code itself to retrieve it.
+fn add_coverage_data_comment<C: Clone + Comments>(
+    comments: &C,
+    debug_comment_prefix: &str,
+    coverage_data: &FileCoverage,
+    metadata_encoding: MetadataEncoding,
+) {
+    let payload = encode_coverage_data(coverage_data, metadata_encoding);
+
+    comments.add_trailing(
+        Span::dummy_with_cmt().hi,
+        Comment {
+            kind: CommentKind::Block,
+            span: Span::dummy_with_cmt(),
+            text: format!("{}{}", debug_comment_prefix, payload).into(),
+        },
+    );
+}
+
+/// Creates a function declaration for the coverage collection delegates the registration
+/// to the shared coverage runtime, instead of inlining the global lookup in every file.
+///
+/// The runtime fn is called as `runtime(coverageVariable, coverageData)`: it should register
+/// `coverageData` under `global[coverageVariable][coverageData.path]` unless an entry with the
+/// same `hash` exists, and return the registered coverage object to increase counters against.
+pub fn create_coverage_runtime_fn_decl<C: Clone + Comments>(
+    runtime_ident: &Ident,
+    cov_fn_ident: &Ident,
+    coverage_data: &FileCoverage,
+    comments: &C,
+    counter_idents: &CounterIdents,
+    instrument_options: &InstrumentOptions,
+) -> Stmt {
+    let coverage_variable = instrument_options.namespaced_coverage_variable();
+    let mut stmts = vec![];
 
-    if attach_debug_comment {
-        let coverage_data_json_str =
-            serde_json::to_string(coverage_data).expect("Should able to serialize coverage data");
-
-        // Append coverage data as stringified JSON comments at the bottom of transformed code.
-        // Currently plugin does not have way to pass any other data to the host except transformed program.
-        // This attaches arbitary data to the transformed code itself to retrieve it.
-        comments.add_trailing(
-            Span::dummy_with_cmt().hi,
-            Comment {
-                kind: CommentKind::Block,
-                span: Span::dummy_with_cmt(),
-                text: format!("__coverage_data_json_comment__::{}", coverage_data_json_str).into(),
-            },
+    let (_, coverage_data_object) = create_coverage_data_object(
+        coverage_data,
+        counter_idents,
+        instrument_options.typed_array_fn_counters,
+        instrument_options.track_lines,
+    );
+
+    // var coverageData = INITIAL;
+    stmts.push(create_assignment_stmt(
+        &IDENT_COVERAGE_DATA,
+        coverage_data_object,
+    ));
+
+    // var actualCoverage = $runtime($coverage_variable, coverageData);
+    let actual_coverage_ident = Ident::new("actualCoverage".into(), DUMMY_SP, Default::default());
+    stmts.push(create_assignment_stmt(
+        &actual_coverage_ident,
+        Expr::Call(CallExpr {
+            callee: Callee::Expr(Box::new(Expr::Ident(runtime_ident.clone()))),
+            args: vec![
+                Expr::Lit(Lit::Str(Str {
+                    value: coverage_variable.into(),
+                    ..Str::dummy()
+                }))
+                .into(),
+                Expr::Ident(IDENT_COVERAGE_DATA.clone()).into(),
+            ],
+            ..CallExpr::dummy()
+        }),
+    ));
+
+    stmts.push(create_coverage_fn_reassign_stmt(
+        cov_fn_ident,
+        &actual_coverage_ident,
+    ));
+
+    if let Some(debug_comment_prefix) = instrument_options.debug_comment_prefix() {
+        add_coverage_data_comment(
+            comments,
+            debug_comment_prefix,
+            coverage_data,
+            instrument_options.metadata_encoding,
         );
     }
 
-    stmts.push(Stmt::Return(ret));
+    stmts.push(Stmt::Return(ReturnStmt {
+        span: DUMMY_SP,
+        arg: Some(Box::new(Expr::Ident(actual_coverage_ident))),
+    }));
 
-    // moduleitem for fn decl includes body defined above
     Stmt::Decl(Decl::Fn(FnDecl {
         ident: cov_fn_ident.clone(),
         declare: false,
@@ -0,0 +1,66 @@
+use istanbul_oxide::FileCoverage;
+use swc_core::common::{
+    comments::{Comment, CommentKind},
+    Span,
+};
+
+/// Prefix of the comment carrying the summary of instrumented counters.
+pub const COVERAGE_SUMMARY_COMMENT_PREFIX: &str = "__coverage_summary__::";
+
+/// Creates a comment with the total number of statements, branches (branch paths) and functions
+/// instrumented for the file, like
+/// `__coverage_summary__::{"statements":3,"branches":2,"functions":1}`.
+/// These are static counts of the counters, not the executed ones.
+pub fn create_coverage_summary_comment(coverage_data: &FileCoverage) -> Comment {
+    let branches = coverage_data
+        .branch_map
+        .values()
+        .map(|branch| branch.locations.len())
+        .sum::<usize>();
+    let summary = format!(
+        r#"{{"statements":{},"branches":{},"functions":{}}}"#,
+        coverage_data.statement_map.len(),
+        branches,
+        coverage_data.fn_map.len()
+    );
+
+    Comment {
+        kind: CommentKind::Block,
+        span: Span::dummy_with_cmt(),
+        text: format!("{}{}", COVERAGE_SUMMARY_COMMENT_PREFIX, summary).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use istanbul_oxide::{BranchType, Range};
+    use swc_core::common::GLOBALS;
+
+    use crate::source_coverage::SourceCoverage;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_create_summary_matching_map_lengths() {
+        let mut coverage_data = SourceCoverage::new("anon".to_string(), false);
+        let dummy_range = Range::new(1, 0, 1, 10);
+
+        coverage_data.new_statement(&dummy_range);
+        coverage_data.new_statement(&dummy_range);
+        coverage_data.new_function(&Some("foo".to_string()), &dummy_range, &dummy_range);
+        let branch = coverage_data.new_branch(BranchType::CondExpr, &dummy_range, false);
+        coverage_data.add_branch_path(branch, &dummy_range);
+        coverage_data.add_branch_path(branch, &dummy_range);
+
+        let comment = GLOBALS.set(&Default::default(), || {
+            create_coverage_summary_comment(coverage_data.as_ref())
+        });
+
+        assert_eq!(
+            &*comment.text,
+            r#"__coverage_summary__::{"statements":2,"branches":2,"functions":1}"#
+        );
+    }
+}
@@ -1,40 +1,74 @@
 use swc_core::{
     common::{util::take::Take, DUMMY_SP},
     ecma::{ast::*, utils::quote_ident},
+    quote,
 };
 
 use crate::constants::idents::IDENT_GLOBAL;
 
 use super::create_assignment_stmt::create_assignment_stmt;
 
-/// Creates an assignment statement for the global scope lookup function
-/// `var global = new Function("return $global_coverage_scope")();`
+/// Scope value resolves the global scope via the well-known global references.
+pub const COVERAGE_GLOBAL_SCOPE_AUTO: &str = "auto";
+
+/// Creates an assignment statement for the global scope lookup.
+/// If the scope is `auto`, well-known global references are tried first
+/// ```js
+/// var global = typeof globalThis !== "undefined" ? globalThis
+///   : typeof self !== "undefined" ? self
+///   : typeof window !== "undefined" ? window
+///   : (new ((function(){}).constructor)("return this"))();
+/// ```
+/// so runtimes disallowing the `Function` constructor (CSP, sandboxed workers) can still resolve the global scope.
+/// Node's `global` is not looked up, as it refers to the hoisted `var global` itself in here.
+/// Node versions without `globalThis` take the `Function` constructor fallback instead.
+/// Otherwise, the given scope is evaluated as-is same as istanbul's `new Function`
+/// ```js
+/// var global = (new ((function(){}).constructor)("return $global_coverage_scope"))();
+/// ```
 pub fn create_global_stmt_template(coverage_global_scope: &str) -> Stmt {
     // Note: we don't support function template based on scoped binding
     // like https://github.com/istanbuljs/istanbuljs/blob/c7693d4608979ab73ebb310e0a1647e2c51f31b6/packages/istanbul-lib-instrument/src/visitor.js#L793=
     // due to scope checking is tricky.
     let fn_ctor = quote_ident!(Default::default(), "((function(){}).constructor)");
 
+    let is_auto = coverage_global_scope == COVERAGE_GLOBAL_SCOPE_AUTO;
+    let scope = if is_auto {
+        "this"
+    } else {
+        coverage_global_scope
+    };
+
     let expr = Expr::New(NewExpr {
         callee: Box::new(Expr::Ident(fn_ctor)),
         args: Some(vec![ExprOrSpread {
             spread: None,
             expr: Box::new(Expr::Lit(Lit::Str(Str {
-                value: format!("return {}", coverage_global_scope).into(),
+                value: format!("return {}", scope).into(),
                 ..Str::dummy()
             }))),
         }]),
         ..NewExpr::dummy()
     });
 
+    let fn_global = Expr::Call(CallExpr {
+        callee: Callee::Expr(Box::new(Expr::Paren(ParenExpr {
+            span: DUMMY_SP,
+            expr: Box::new(expr),
+        }))),
+        ..CallExpr::dummy()
+    });
+
+    if !is_auto {
+        return create_assignment_stmt(&IDENT_GLOBAL, fn_global);
+    }
+
     create_assignment_stmt(
         &IDENT_GLOBAL,
-        Expr::Call(CallExpr {
-            callee: Callee::Expr(Box::new(Expr::Paren(ParenExpr {
-                span: DUMMY_SP,
-                expr: Box::new(expr),
-            }))),
-            ..CallExpr::dummy()
-        }),
+        quote!(
+            r#"typeof globalThis !== "undefined" ? globalThis : typeof self !== "undefined" ? self : typeof window !== "undefined" ? window : $fn_global"#
+                as Expr,
+            fn_global: Expr = fn_global
+        ),
     )
 }
@@ -0,0 +1,27 @@
+use istanbul_oxide::Range;
+use swc_core::common::{
+    comments::{Comment, CommentKind},
+    Span,
+};
+
+/// Prefix of the comment listing the nodes not able to be instrumented under `strict_coverage`.
+pub const COVERAGE_SKIPPED_COMMENT_PREFIX: &str = "__coverage_skipped__::";
+
+/// Creates a comment lists the nodes skipped by the instrumentation with their locations, like
+/// `__coverage_skipped__::class method with non-identifier key (3:2), class method with non-identifier key (7:2)`.
+/// Locations are the start of the node, 1-based line and 0-based column.
+pub fn create_skipped_nodes_comment(skipped_nodes: &[(String, Range)]) -> Comment {
+    let nodes = skipped_nodes
+        .iter()
+        .map(|(node, range)| format!("{} ({}:{})", node, range.start.line, range.start.column))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Comment {
+        kind: CommentKind::Block,
+        span: Span::dummy_with_cmt(),
+        text: format!("{}{}", COVERAGE_SKIPPED_COMMENT_PREFIX, nodes)
+            .replace("*/", "*\\/")
+            .into(),
+    }
+}
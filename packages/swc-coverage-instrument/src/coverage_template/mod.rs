@@ -3,4 +3,7 @@
 pub(crate) mod create_assignment_stmt;
 pub(crate) mod create_coverage_data_object;
 pub(crate) mod create_coverage_fn_decl;
+pub(crate) mod create_coverage_summary_comment;
 pub(crate) mod create_global_stmt_template;
+pub(crate) mod create_skipped_nodes_comment;
+pub(crate) mod strip_coverage_comment;
@@ -0,0 +1,88 @@
+use swc_core::{common::comments::Comments, ecma::ast::Program};
+
+use crate::comment_positions::collect_comment_positions;
+
+/// Removes coverage data comments (`{prefix}{json}`, `__coverage_data_json_comment__::{json}` by default)
+/// attached by the instrumentation, leaving any other comments as-is.
+///
+/// Comments are looked up by the node boundaries of the program, which is where the parser attaches
+/// them. This is meant for the instrumented code parsed again, i.e before re-processing or shipping it:
+/// within the instrumentation pass itself the comment is attached to a position not belonging to any node.
+pub fn strip_coverage_comment<C: Comments>(program: &Program, comments: &C, prefix: &str) {
+    for pos in collect_comment_positions(program) {
+        if let Some(mut leading) = comments.take_leading(pos) {
+            leading.retain(|c| !c.text.starts_with(prefix));
+            if !leading.is_empty() {
+                comments.add_leading_comments(pos, leading);
+            }
+        }
+
+        if let Some(mut trailing) = comments.take_trailing(pos) {
+            trailing.retain(|c| !c.text.starts_with(prefix));
+            if !trailing.is_empty() {
+                comments.add_trailing_comments(pos, trailing);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{comments::SingleThreadedComments, FileName, SourceMap},
+        ecma::{
+            ast::EsVersion,
+            parser::{parse_file_as_program, Syntax},
+        },
+    };
+
+    use pretty_assertions::assert_eq;
+
+    use crate::COVERAGE_DATA_JSON_COMMENT_PREFIX;
+
+    use super::*;
+
+    #[test]
+    fn should_strip_coverage_comment_only() {
+        let code = r#"
+/* user leading */
+/*__coverage_data_json_comment__*/
+var a = 1; /*__coverage_data_json_comment__::{}*/ /* user trailing */
+/*__coverage_data_json_comment__::{}*/
+var b = 2;
+/*__coverage_data_json_comment__::{}*/
+"#;
+
+        let source_map: SourceMap = Default::default();
+        let comments = SingleThreadedComments::default();
+        let fm = source_map.new_source_file(FileName::Anon.into(), code.to_string());
+        let program = parse_file_as_program(
+            &fm,
+            Syntax::Es(Default::default()),
+            EsVersion::latest(),
+            Some(&comments),
+            &mut vec![],
+        )
+        .expect("Should able to parse");
+
+        strip_coverage_comment(&program, &comments, COVERAGE_DATA_JSON_COMMENT_PREFIX);
+
+        let (leading, trailing) = comments.borrow_all();
+        let mut remaining = leading
+            .values()
+            .chain(trailing.values())
+            .flatten()
+            .map(|c| c.text.to_string())
+            .collect::<Vec<_>>();
+        remaining.sort();
+
+        assert_eq!(
+            remaining,
+            vec![
+                " user leading ".to_string(),
+                " user trailing ".to_string(),
+                "__coverage_data_json_comment__".to_string(),
+            ]
+        );
+    }
+}
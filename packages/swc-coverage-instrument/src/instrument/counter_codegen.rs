@@ -0,0 +1,120 @@
+use swc_core::ecma::ast::*;
+
+use super::create_increase_counter_expr::{
+    create_accumulate_counter_expr, create_assign_counter_expr, create_increase_counter_expr,
+};
+use crate::IncrementStyle;
+
+/// Creates the counter exprs inserted into the instrumented code.
+///
+/// Default methods emit istanbul's counters like `cov_17709493053001988098().s[0]++`.
+/// Implement this to plug in a custom counter representation, i.e calling a runtime
+/// function instead of increasing the coverage object's counters. Coverage data embedded
+/// in the output is not affected, as well as `bT` counters of `reportLogic`.
+///
+/// `cov_fn_ident` is the ident of the coverage fn of the file, `counter_ident` is the
+/// counter property (`s` / `b` / `f` by default) for the counter being created.
+pub trait CounterCodegen {
+    /// Creates an expr increasing the statement counter `id`.
+    fn statement_increment(&self, cov_fn_ident: &Ident, counter_ident: &Ident, id: u32) -> Expr {
+        create_increase_counter_expr(counter_ident, id, cov_fn_ident, None)
+    }
+
+    /// Creates an expr increasing the path `idx` of the branch counter `id`.
+    fn branch_increment(
+        &self,
+        cov_fn_ident: &Ident,
+        counter_ident: &Ident,
+        id: u32,
+        idx: u32,
+    ) -> Expr {
+        create_increase_counter_expr(counter_ident, id, cov_fn_ident, Some(idx))
+    }
+
+    /// Creates an expr increasing the function counter `id`.
+    fn function_increment(&self, cov_fn_ident: &Ident, counter_ident: &Ident, id: u32) -> Expr {
+        create_increase_counter_expr(counter_ident, id, cov_fn_ident, None)
+    }
+
+    /// Determine if given expr is a counter created by this codegen. Visitors use this to
+    /// skip the counters already inserted, custom impls should override this accordingly.
+    fn is_counter_expr(&self, cov_fn_ident: &Ident, expr: &Expr) -> bool {
+        let member = match expr {
+            Expr::Update(UpdateExpr { arg, .. }) => match &**arg {
+                Expr::Member(member) => member,
+                _ => return false,
+            },
+            Expr::Assign(AssignExpr {
+                op: AssignOp::AddAssign | AssignOp::Assign,
+                left: AssignTarget::Simple(SimpleAssignTarget::Member(member)),
+                ..
+            }) => member,
+            _ => return false,
+        };
+
+        if let Expr::Member(MemberExpr { obj, .. }) = &*member.obj {
+            if let Expr::Call(CallExpr {
+                callee: Callee::Expr(callee),
+                ..
+            }) = &**obj
+            {
+                if let Expr::Ident(ident) = &**callee {
+                    return ident == cov_fn_ident;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Default counter codegen, creates istanbul compatible counters.
+#[derive(Clone, Debug, Default)]
+pub struct DefaultCounterCodegen {
+    /// Uses `+=` accumulation for the function counters backed by typed array.
+    pub typed_array_fn_counters: bool,
+    /// Form of the increments, `cov().s[0]++` or `cov().s[0] = cov().s[0] + 1`.
+    pub increment_style: IncrementStyle,
+}
+
+impl DefaultCounterCodegen {
+    fn increment(
+        &self,
+        cov_fn_ident: &Ident,
+        counter_ident: &Ident,
+        id: u32,
+        idx: Option<u32>,
+    ) -> Expr {
+        match self.increment_style {
+            IncrementStyle::Increment => {
+                create_increase_counter_expr(counter_ident, id, cov_fn_ident, idx)
+            }
+            IncrementStyle::Assign => {
+                create_assign_counter_expr(counter_ident, id, cov_fn_ident, idx)
+            }
+        }
+    }
+}
+
+impl CounterCodegen for DefaultCounterCodegen {
+    fn statement_increment(&self, cov_fn_ident: &Ident, counter_ident: &Ident, id: u32) -> Expr {
+        self.increment(cov_fn_ident, counter_ident, id, None)
+    }
+
+    fn branch_increment(
+        &self,
+        cov_fn_ident: &Ident,
+        counter_ident: &Ident,
+        id: u32,
+        idx: u32,
+    ) -> Expr {
+        self.increment(cov_fn_ident, counter_ident, id, Some(idx))
+    }
+
+    fn function_increment(&self, cov_fn_ident: &Ident, counter_ident: &Ident, id: u32) -> Expr {
+        if self.typed_array_fn_counters {
+            create_accumulate_counter_expr(counter_ident, id, cov_fn_ident)
+        } else {
+            self.increment(cov_fn_ident, counter_ident, id, None)
+        }
+    }
+}
@@ -1,15 +1,13 @@
 use swc_core::{common::DUMMY_SP, ecma::ast::*};
 
-/// Creates a expr like `cov_17709493053001988098().s[0]++;`
-/// idx indicates index of vec-based counters (i.e branches).
-/// If it exists, creates a expr with idx like
-/// 1cov_17709493053001988098().b[0][idx]++;` instead.
-pub fn create_increase_counter_expr(
+/// Creates a member expr to the counter like `cov_17709493053001988098().s[0]`,
+/// or `cov_17709493053001988098().b[0][idx]` if idx exists.
+fn create_counter_member_expr(
     type_ident: &Ident,
     id: u32,
     var_name: &Ident,
     idx: Option<u32>,
-) -> Expr {
+) -> MemberExpr {
     let call = CallExpr {
         span: DUMMY_SP,
         callee: Callee::Expr(Box::new(Expr::Ident(var_name.clone()))),
@@ -37,7 +35,7 @@ pub fn create_increase_counter_expr(
         }),
     };
 
-    let expr = if let Some(idx) = idx {
+    if let Some(idx) = idx {
         MemberExpr {
             span: DUMMY_SP,
             obj: Box::new(Expr::Member(expr)),
@@ -52,12 +50,71 @@ pub fn create_increase_counter_expr(
         }
     } else {
         expr
-    };
+    }
+}
 
+/// Creates a expr like `cov_17709493053001988098().s[0]++;`
+/// idx indicates index of vec-based counters (i.e branches).
+/// If it exists, creates a expr with idx like
+/// 1cov_17709493053001988098().b[0][idx]++;` instead.
+pub fn create_increase_counter_expr(
+    type_ident: &Ident,
+    id: u32,
+    var_name: &Ident,
+    idx: Option<u32>,
+) -> Expr {
     Expr::Update(UpdateExpr {
         span: DUMMY_SP,
         op: UpdateOp::PlusPlus,
         prefix: false,
-        arg: Box::new(Expr::Member(expr)),
+        arg: Box::new(Expr::Member(create_counter_member_expr(
+            type_ident, id, var_name, idx,
+        ))),
+    })
+}
+
+/// Creates a expr like `cov_17709493053001988098().f[0] += 1;`
+/// Used for the counters backed by typed array.
+pub fn create_accumulate_counter_expr(type_ident: &Ident, id: u32, var_name: &Ident) -> Expr {
+    Expr::Assign(AssignExpr {
+        span: DUMMY_SP,
+        op: AssignOp::AddAssign,
+        left: AssignTarget::Simple(SimpleAssignTarget::Member(create_counter_member_expr(
+            type_ident, id, var_name, None,
+        ))),
+        right: Box::new(Expr::Lit(Lit::Num(Number {
+            span: DUMMY_SP,
+            value: 1.0,
+            raw: None,
+        }))),
+    })
+}
+
+/// Creates a expr like `cov_17709493053001988098().s[0] = cov_17709493053001988098().s[0] + 1;`
+/// Same as `create_increase_counter_expr`, but reads and writes the counter via plain assignment.
+pub fn create_assign_counter_expr(
+    type_ident: &Ident,
+    id: u32,
+    var_name: &Ident,
+    idx: Option<u32>,
+) -> Expr {
+    Expr::Assign(AssignExpr {
+        span: DUMMY_SP,
+        op: AssignOp::Assign,
+        left: AssignTarget::Simple(SimpleAssignTarget::Member(create_counter_member_expr(
+            type_ident, id, var_name, idx,
+        ))),
+        right: Box::new(Expr::Bin(BinExpr {
+            span: DUMMY_SP,
+            op: BinaryOp::Add,
+            left: Box::new(Expr::Member(create_counter_member_expr(
+                type_ident, id, var_name, idx,
+            ))),
+            right: Box::new(Expr::Lit(Lit::Num(Number {
+                span: DUMMY_SP,
+                value: 1.0,
+                raw: None,
+            }))),
+        })),
     })
 }
@@ -0,0 +1,303 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use swc_core::{
+    common::{
+        comments::{Comments, SingleThreadedComments},
+        source_map::DefaultSourceMapGenConfig,
+        FileName, SourceMap, Spanned, GLOBALS,
+    },
+    ecma::{
+        ast::{EsVersion, Program},
+        codegen::{text_writer::JsWriter, Emitter, Node},
+        parser::{parse_file_as_program, EsSyntax, Syntax},
+        transforms::base::fixer::fixer,
+        visit::VisitMutWith,
+    },
+};
+
+use crate::{create_coverage_instrumentation_visitor, FileCoverage, InstrumentOptions};
+
+/// Result of `instrument_sync`, shaped same as istanbul-lib-instrument's
+/// `instrumentSync` output with `lastFileCoverage()`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstrumentResult {
+    pub code: String,
+    /// Serialized source map of the instrumented code.
+    pub map: Option<String>,
+    pub file_coverage: FileCoverage,
+}
+
+/// Parse given code, instrument it and print back.
+/// Returns the instrumented code with its source map and the coverage data of the file.
+pub fn instrument_sync(
+    code: &str,
+    filename: &str,
+    instrument_options: InstrumentOptions,
+) -> Result<InstrumentResult, String> {
+    GLOBALS.set(&Default::default(), || {
+        instrument_sync_inner(code, filename, instrument_options)
+    })
+}
+
+/// Moves the comments the instrumentation attached to the positions reserved for comments
+/// (coverage data, summary, skipped nodes) to the end of the program, as the emitter only
+/// prints comments attached to the positions of the nodes.
+fn attach_reserved_comments(comments: &SingleThreadedComments, program: &Program) {
+    let mut reserved = {
+        let (leading, trailing) = comments.borrow_all();
+        leading
+            .keys()
+            .chain(trailing.keys())
+            .copied()
+            .filter(|pos| pos.is_reserved_for_comments())
+            .collect::<Vec<_>>()
+    };
+    // Reserved positions are allocated incrementally, keeps the order comments were added.
+    reserved.sort();
+    reserved.dedup();
+
+    let hi = program.span().hi;
+    for pos in reserved {
+        for taken in [comments.take_leading(pos), comments.take_trailing(pos)]
+            .into_iter()
+            .flatten()
+        {
+            comments.add_trailing_comments(hi, taken);
+        }
+    }
+}
+
+fn instrument_sync_inner(
+    code: &str,
+    filename: &str,
+    instrument_options: InstrumentOptions,
+) -> Result<InstrumentResult, String> {
+    let source_map: Arc<SourceMap> = Default::default();
+    let comments = SingleThreadedComments::default();
+    let fm = source_map.new_source_file(FileName::Real(filename.into()).into(), code.to_string());
+
+    let mut program = parse_file_as_program(
+        &fm,
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        }),
+        EsVersion::latest(),
+        Some(&comments),
+        &mut vec![],
+    )
+    .map_err(|err| format!("Failed to parse {}: {:?}", filename, err.kind()))?;
+
+    let sources = crate::count_concatenated_sources(&source_map, &comments, &program);
+    if sources > 1 {
+        return Err(format!(
+            "{} contains {} sources concatenated by sourceMappingURL comments, instrument each file before concatenating",
+            filename, sources
+        ));
+    }
+
+    let omit_counter_parens = instrument_options.omit_counter_parens;
+    let mut visitor = create_coverage_instrumentation_visitor(
+        source_map.clone(),
+        comments.clone(),
+        instrument_options,
+        filename.to_string(),
+    );
+    program.visit_mut_with(&mut visitor);
+
+    // Bare sequence exprs of the counters need parens in some positions, same as swc's pipeline does.
+    if omit_counter_parens {
+        program.visit_mut_with(&mut fixer(Some(&comments)));
+    }
+    attach_reserved_comments(&comments, &program);
+
+    let mut buf = vec![];
+    let mut src_map_buf = vec![];
+    {
+        let mut emitter = Emitter {
+            cfg: Default::default(),
+            cm: source_map.clone(),
+            comments: Some(&comments),
+            wr: JsWriter::new(source_map.clone(), "\n", &mut buf, Some(&mut src_map_buf)),
+        };
+        program
+            .emit_with(&mut emitter)
+            .map_err(|err| format!("Failed to emit {}: {}", filename, err))?;
+    }
+
+    let mut map = vec![];
+    source_map
+        .build_source_map_with_config(&src_map_buf, None, DefaultSourceMapGenConfig)
+        .to_writer(&mut map)
+        .map_err(|err| format!("Failed to emit source map {}: {}", filename, err))?;
+
+    Ok(InstrumentResult {
+        code: String::from_utf8(buf).map_err(|err| err.to_string())?,
+        map: String::from_utf8(map).ok(),
+        file_coverage: visitor.file_coverage(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_return_file_coverage_matches_embedded_metadata() {
+        let code = r#"var x = args[0] ? 1 : 2;
+function foo() {
+  return x;
+}
+"#;
+        let result = instrument_sync(
+            code,
+            "instrument-sync.js",
+            InstrumentOptions {
+                debug_initial_coverage_comment: true,
+                ..Default::default()
+            },
+        )
+        .expect("Should able to instrument");
+
+        let comment_start = result
+            .code
+            .find(crate::COVERAGE_DATA_JSON_COMMENT_PREFIX)
+            .expect("Should have embedded coverage data")
+            + crate::COVERAGE_DATA_JSON_COMMENT_PREFIX.len();
+        let comment_end = comment_start
+            + result.code[comment_start..]
+                .find("*/")
+                .expect("Should have comment terminator");
+        let embedded: serde_json::Value =
+            serde_json::from_str(&result.code[comment_start..comment_end])
+                .expect("Should able to parse embedded coverage data");
+
+        let file_coverage = serde_json::to_value(&result.file_coverage).unwrap();
+        assert_eq!(file_coverage["statementMap"], embedded["statementMap"]);
+        assert_eq!(result.file_coverage.statement_map.len(), 2);
+        assert!(result.map.is_some());
+    }
+
+    #[test]
+    fn should_emit_global_scope_fallbacks_for_auto_scope() {
+        let result = instrument_sync("var x = 1;", "global-scope.js", Default::default())
+            .expect("Should able to instrument");
+
+        for fallback in [
+            r#"typeof globalThis !== "undefined" ? globalThis"#,
+            r#"typeof self !== "undefined" ? self"#,
+            r#"typeof window !== "undefined" ? window"#,
+            r#"(new ((function(){}).constructor)("return this"))()"#,
+        ] {
+            assert!(
+                result.code.contains(fallback),
+                "Should contain `{}` in {}",
+                fallback,
+                result.code
+            );
+        }
+        // `global` would be the hoisted `var global` itself.
+        assert!(!result.code.contains("typeof global "));
+
+        let result = instrument_sync(
+            "var x = 1;",
+            "global-scope.js",
+            InstrumentOptions {
+                coverage_global_scope: "globalThis".to_string(),
+                ..Default::default()
+            },
+        )
+        .expect("Should able to instrument");
+
+        assert!(result.code.contains(
+            r#"var global = (new ((function(){}).constructor)("return globalThis"))();"#
+        ));
+        assert!(!result.code.contains("typeof window"));
+    }
+
+    #[test]
+    fn should_reject_concatenated_sources() {
+        let code = r#"var a = 1;
+//# sourceMappingURL=a.js.map
+var b = 2;
+//# sourceMappingURL=b.js.map
+"#;
+        let err = match instrument_sync(code, "bundle.js", Default::default()) {
+            Ok(_) => panic!("Should not instrument concatenated sources"),
+            Err(err) => err,
+        };
+        assert!(err.contains("bundle.js contains 2 sources"), "{}", err);
+
+        // A trailing sourceMappingURL of a single file is not a concatenation.
+        let code = "var a = 1;\n//# sourceMappingURL=a.js.map\n";
+        assert!(instrument_sync(code, "a.js", Default::default()).is_ok());
+    }
+
+    #[test]
+    fn should_not_count_source_mapping_url_in_literals() {
+        let code = r#"var template = `
+//# sourceMappingURL=a.js.map
+`;
+var str = "\
+//# sourceMappingURL=b.js.map";
+//# sourceMappingURL=c.js.map
+"#;
+        assert!(instrument_sync(code, "c.js", Default::default()).is_ok());
+    }
+
+    struct NormalizeParens;
+
+    impl swc_core::ecma::visit::VisitMut for NormalizeParens {
+        fn visit_mut_span(&mut self, span: &mut swc_core::common::Span) {
+            *span = swc_core::common::DUMMY_SP;
+        }
+
+        fn visit_mut_expr(&mut self, expr: &mut swc_core::ecma::ast::Expr) {
+            expr.visit_mut_children_with(self);
+            if let swc_core::ecma::ast::Expr::Paren(paren) = expr {
+                *expr = *paren.expr.clone();
+            }
+        }
+    }
+
+    fn parse_normalized(code: &str) -> swc_core::ecma::ast::Program {
+        let source_map: Arc<SourceMap> = Default::default();
+        let fm = source_map.new_source_file(FileName::Anon.into(), code.to_string());
+        let mut program = parse_file_as_program(
+            &fm,
+            Default::default(),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .expect("Should able to parse instrumented code");
+        program.visit_mut_with(&mut NormalizeParens);
+        program
+    }
+
+    #[test]
+    fn should_parse_identically_without_counter_parens() {
+        let code = r#"var a = args[0] ? 1 : 2, b = function () {};
+foo(args[1] || 3, [a, b]);
+"#;
+        let instrument = |omit_counter_parens: bool| {
+            instrument_sync(
+                code,
+                "omit-counter-parens.js",
+                InstrumentOptions {
+                    omit_counter_parens,
+                    ..Default::default()
+                },
+            )
+            .expect("Should able to instrument")
+            .code
+        };
+
+        assert_eq!(
+            parse_normalized(&instrument(true)),
+            parse_normalized(&instrument(false))
+        );
+    }
+}
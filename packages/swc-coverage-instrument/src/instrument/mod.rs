@@ -1,2 +1,5 @@
+pub mod counter_codegen;
 pub mod create_increase_counter_expr;
 pub mod create_increase_true_expr;
+#[cfg(feature = "instrument_sync")]
+pub mod instrument_sync;
@@ -4,28 +4,48 @@ mod constants;
 mod source_coverage;
 
 mod instrument;
-use instrument::create_increase_counter_expr::create_increase_counter_expr;
+pub use instrument::counter_codegen::{CounterCodegen, DefaultCounterCodegen};
 use instrument::create_increase_true_expr::create_increase_true_expr;
+#[cfg(feature = "instrument_sync")]
+pub use instrument::instrument_sync::{instrument_sync, InstrumentResult};
 
 mod coverage_template;
 use coverage_template::create_assignment_stmt::create_assignment_stmt;
 use coverage_template::create_coverage_data_object::create_coverage_data_object;
+pub use coverage_template::create_coverage_fn_decl::COVERAGE_DATA_JSON_COMMENT_PREFIX;
 use coverage_template::create_coverage_fn_decl::*;
+use coverage_template::create_coverage_summary_comment::create_coverage_summary_comment;
 use coverage_template::create_global_stmt_template::create_global_stmt_template;
+pub use coverage_template::create_global_stmt_template::COVERAGE_GLOBAL_SCOPE_AUTO;
+use coverage_template::create_skipped_nodes_comment::create_skipped_nodes_comment;
+pub use coverage_template::create_skipped_nodes_comment::COVERAGE_SKIPPED_COMMENT_PREFIX;
+pub use coverage_template::strip_coverage_comment::strip_coverage_comment;
 use source_coverage::SourceCoverage;
 
 #[macro_use]
 mod macros;
 
 mod visitors;
-pub use visitors::coverage_visitor::{create_coverage_instrumentation_visitor, CoverageVisitor};
+pub use visitors::coverage_visitor::{
+    create_coverage_instrumentation_visitor, get_instrumented_ranges, CounterKind, CoverageVisitor,
+};
 mod options;
 pub use options::instrument_options::*;
 
 mod utils;
+use utils::comment_positions;
+pub use utils::concatenated_sources::count_concatenated_sources;
+use utils::existing_coverage;
 use utils::hint_comments;
 use utils::lookup_range;
+// Exposed for the benches comparing the memoized position lookups against the uncached baseline.
+#[doc(hidden)]
+pub use utils::lookup_range::{get_range_from_span, get_range_from_span_uncached, LookupCache};
+pub use utils::metadata_encoding::{decode_coverage_data, encode_coverage_data};
+use utils::minified;
 pub use utils::node::Node;
+use utils::pre_instrumented;
+use utils::trivial_stmt;
 
 // Reexports
 pub use istanbul_oxide::types::*;
@@ -19,6 +19,11 @@ macro_rules! create_instrumentation_visitor {
             cov_fn_ident: Ident,
             cov_fn_temp_ident: Ident,
             instrument_options: crate::InstrumentOptions,
+            counter_idents: crate::constants::idents::CounterIdents,
+            // Creates counter exprs to be inserted, shared with the child visitors.
+            counter_codegen: std::rc::Rc<dyn crate::CounterCodegen>,
+            // Memoized source map lookups, shared with the child visitors.
+            lookup_cache: std::rc::Rc<crate::lookup_range::LookupCache>,
             // Current visitor state to hold stmts to be prepended by parent node.
             pub before: Vec<Stmt>,
             nodes: Vec<crate::Node>,
@@ -31,18 +36,25 @@ macro_rules! create_instrumentation_visitor {
                 source_map: std::sync::Arc<S>,
                 comments: C,
                 cov: std::rc::Rc<std::cell::RefCell<crate::SourceCoverage>>,
+                cov_fn_ident: Ident,
+                cov_fn_temp_ident: Ident,
                 instrument_options: crate::InstrumentOptions,
                 nodes: Vec<crate::Node>,
                 should_ignore: Option<crate::hint_comments::IgnoreScope>,
+                counter_codegen: std::rc::Rc<dyn crate::CounterCodegen>,
+                lookup_cache: std::rc::Rc<crate::lookup_range::LookupCache>,
                 $($field: $t,)*
             ) -> $name<C, S> {
                 $name {
                     source_map: source_map,
                     comments: comments,
                     cov: cov,
-                    cov_fn_ident: crate::COVERAGE_FN_IDENT.get().expect("Coverage fn Ident should be initialized already").clone(),
-                    cov_fn_temp_ident: crate::COVERAGE_FN_TRUE_TEMP_IDENT.get().expect("Coverage fn Ident should be initialized already").clone(),
+                    cov_fn_ident,
+                    cov_fn_temp_ident,
+                    counter_idents: crate::constants::idents::CounterIdents::from_options(&instrument_options.counter_idents),
                     instrument_options: instrument_options,
+                    counter_codegen,
+                    lookup_cache,
                     before: vec![],
                     nodes: nodes,
                     should_ignore,
@@ -50,6 +62,11 @@ macro_rules! create_instrumentation_visitor {
                 }
             }
 
+            // Converts span into istanbul's range, reusing the positions looked up already.
+            fn lookup_range(&self, span: &Span) -> crate::Range {
+                crate::lookup_range::get_range_from_span(&self.source_map, &self.lookup_cache, span)
+            }
+
             // Display current nodes.
             fn print_node(&self) -> String {
                 if self.nodes.len() > 0 {
@@ -162,14 +179,19 @@ macro_rules! create_instrumentation_visitor {
          on_enter!(BinExpr);
          on_enter!(VarDeclarator);
          on_enter!(VarDecl);
+         on_enter!(UsingDecl);
          on_enter!(CondExpr);
+         on_enter!(SeqExpr);
          on_enter!(ExprStmt);
          on_enter!(IfStmt);
          on_enter!(LabeledStmt);
          on_enter!(ContinueStmt);
+         on_enter!(TsEnumDecl);
          on_enter!(ClassProp);
          on_enter!(PrivateProp);
+         on_enter!(AutoAccessor);
          on_enter!(ClassMethod);
+         on_enter!(Constructor);
          on_enter!(ArrowExpr);
          on_enter!(ForStmt);
          on_enter!(ForOfStmt);
@@ -188,6 +210,8 @@ macro_rules! create_instrumentation_visitor {
          on_enter!(ExportDefaultDecl);
          on_enter!(DebuggerStmt);
          on_enter!(AssignPat);
+         on_enter!(AssignPatProp);
+         on_enter!(AssignExpr);
          on_enter!(GetterProp);
          on_enter!(SetterProp);
     }
@@ -6,56 +6,67 @@ macro_rules! instrumentation_branch_wrap_counter_helper {
         //}
         #[tracing::instrument(skip_all)]
         fn replace_expr_with_stmt_counter(&mut self, expr: &mut Expr) {
-            self.replace_expr_with_counter(expr, |cov, cov_fn_ident, range| {
-                let idx = cov.new_statement(&range);
-                crate::create_increase_counter_expr(
-                    &crate::constants::idents::IDENT_S,
-                    idx,
-                    cov_fn_ident,
-                    None,
-                )
+            let ident_s = self.counter_idents.s.clone();
+            let counter_codegen = self.counter_codegen.clone();
+            let range = self.lookup_range(&expr.span());
+            self.replace_expr_with_counter(expr, &range, |cov, cov_fn_ident, range| {
+                let idx = cov.try_new_statement(&range)?;
+                Some(counter_codegen.statement_increment(cov_fn_ident, &ident_s, idx))
             });
         }
 
         #[tracing::instrument(skip_all)]
-        fn replace_expr_with_branch_counter(&mut self, expr: &mut Expr, branch: u32) {
-            self.replace_expr_with_counter(expr, |cov, cov_fn_ident, range| {
+        fn replace_expr_with_branch_counter(
+            &mut self,
+            expr: &mut Expr,
+            branch: u32,
+            range: &crate::Range,
+        ) {
+            let ident_b = self.counter_idents.b.clone();
+            let counter_codegen = self.counter_codegen.clone();
+            self.replace_expr_with_counter(expr, range, |cov, cov_fn_ident, range| {
                 let idx = cov.add_branch_path(branch, &range);
 
-                crate::create_increase_counter_expr(
-                    &crate::constants::idents::IDENT_B,
-                    branch,
-                    cov_fn_ident,
-                    Some(idx),
-                )
+                Some(counter_codegen.branch_increment(cov_fn_ident, &ident_b, branch, idx))
             });
         }
 
-        // Base wrapper fn to replace given expr to wrapped paren expr with counter
+        // Base wrapper fn to replace given expr to wrapped paren expr with counter,
+        // which is registered with the given range.
+        // Expr is left as-is if no counter is created, i.e outside of the changed lines.
         #[tracing::instrument(skip_all)]
-        fn replace_expr_with_counter<F>(&mut self, expr: &mut Expr, get_counter: F)
-        where
+        fn replace_expr_with_counter<F>(
+            &mut self,
+            expr: &mut Expr,
+            range: &crate::Range,
+            get_counter: F,
+        ) where
             F: core::ops::Fn(
                 &mut crate::source_coverage::SourceCoverage,
                 &Ident,
                 &crate::Range,
-            ) -> Expr,
+            ) -> Option<Expr>,
         {
-            let span = expr.span();
-            let init_range = crate::lookup_range::get_range_from_span(&self.source_map, &span);
-            let prepend_expr =
-                get_counter(&mut self.cov.borrow_mut(), &self.cov_fn_ident, &init_range);
+            let prepend_expr = get_counter(&mut self.cov.borrow_mut(), &self.cov_fn_ident, range);
+            let prepend_expr = match prepend_expr {
+                Some(prepend_expr) => prepend_expr,
+                None => return,
+            };
 
-            let paren_expr = Expr::Paren(ParenExpr {
+            let seq_expr = Expr::Seq(SeqExpr {
                 span: swc_core::common::DUMMY_SP,
-                expr: Box::new(Expr::Seq(SeqExpr {
-                    span: swc_core::common::DUMMY_SP,
-                    exprs: vec![Box::new(prepend_expr), Box::new(expr.take())],
-                })),
+                exprs: vec![Box::new(prepend_expr), Box::new(expr.take())],
             });
 
             // replace init with increase expr + init seq
-            *expr = paren_expr;
+            *expr = if self.instrument_options.omit_counter_parens {
+                seq_expr
+            } else {
+                Expr::Paren(ParenExpr {
+                    span: swc_core::common::DUMMY_SP,
+                    expr: Box::new(seq_expr),
+                })
+            };
         }
 
         /// Attempt to wrap expression with branch increase counter.
@@ -69,6 +80,11 @@ macro_rules! instrumentation_branch_wrap_counter_helper {
                 return;
             }
 
+            // `a /* istanbul ignore next */ || b`
+            if crate::hint_comments::should_ignore_branch_path(&self.comments, &span) {
+                return;
+            }
+
             // Logical expression can have inner logical expression as non-direct child
             // (i.e `args[0] > 0 && (args[0] < 5 || args[0] > 10)`, logical || expr is child of ParenExpr.
             // Try to look up if current expr is the `leaf` of whole logical expr tree.
@@ -81,25 +97,37 @@ macro_rules! instrumentation_branch_wrap_counter_helper {
                     self.source_map.clone(),
                     self.comments.clone(),
                     self.cov.clone(),
+                    self.cov_fn_ident.clone(),
+                    self.cov_fn_temp_ident.clone(),
                     self.instrument_options.clone(),
                     self.nodes.clone(),
                     should_ignore,
+                    self.counter_codegen.clone(),
+                    self.lookup_cache.clone(),
                     branch,
                 );
 
                 expr.visit_mut_children_with(&mut visitor);
             } else {
                 // Now we believe this expr is the leaf of the logical expr tree.
-                // Wrap it with branch counter.
+                // Same as istanbul, each path uses the leaf's own location, not the enclosing
+                // logical expr's. Babel does not have paren expr node, so the location excludes
+                // the parens around the leaf (`a && (b ? c : d)`).
+                // Look it up before visiting the leaf, as it can be replaced by its inner counters.
+                let range = self.lookup_range(&expr.unwrap_parens().span());
+
+                // Visit inside of the leaf first to register its own counters (i.e conditional expr
+                // branches in `a && (b ? c : d)`), then wrap it with branch counter.
+                self.visit_mut_logical_expr_leaf(expr);
+
                 if self.instrument_options.report_logic {
-                    let range = crate::lookup_range::get_range_from_span(&self.source_map, &span);
                     let branch_path_index = self.cov.borrow_mut().add_branch_path(branch, &range);
 
-                    let increase_expr = crate::create_increase_counter_expr(
-                        &crate::constants::idents::IDENT_B,
-                        branch,
+                    let increase_expr = self.counter_codegen.branch_increment(
                         &self.cov_fn_ident,
-                        Some(branch_path_index),
+                        &self.counter_idents.b,
+                        branch,
+                        branch_path_index,
                     );
                     let increase_true_expr = crate::create_increase_true_expr(
                         branch,
@@ -118,11 +146,31 @@ macro_rules! instrumentation_branch_wrap_counter_helper {
                     // replace init with increase expr + init seq
                     *expr = paren_expr;
                 } else {
-                    expr.visit_mut_children_with(self);
-                    self.replace_expr_with_branch_counter(expr, branch);
+                    self.replace_expr_with_branch_counter(expr, branch, &range);
                 }
             }
         }
+
+        /// Visit children of the logical expr leaf with the instrumentation visitor.
+        /// Uses a StmtVisitor instead of self, as the nested logical expr visitor
+        /// only knows how to traverse logical exprs.
+        #[tracing::instrument(skip_all)]
+        fn visit_mut_logical_expr_leaf(&mut self, expr: &mut Expr) {
+            let mut visitor = crate::visitors::stmt_like_visitor::StmtVisitor::new(
+                self.source_map.clone(),
+                self.comments.clone(),
+                self.cov.clone(),
+                self.cov_fn_ident.clone(),
+                self.cov_fn_temp_ident.clone(),
+                self.instrument_options.clone(),
+                self.nodes.clone(),
+                self.should_ignore,
+                self.counter_codegen.clone(),
+                self.lookup_cache.clone(),
+            );
+            expr.visit_mut_children_with(&mut visitor);
+            self.before.extend(visitor.before.drain(..));
+        }
     };
 }
 
@@ -133,20 +181,20 @@ macro_rules! instrumentation_counter_helper {
     () => {
         crate::instrumentation_branch_wrap_counter_helper!();
 
-        #[tracing::instrument(skip(self, span, idx), fields(stmt_id))]
-        fn create_stmt_increase_counter_expr(&mut self, span: &Span, idx: Option<u32>) -> Expr {
-            let stmt_range = crate::lookup_range::get_range_from_span(&self.source_map, span);
+        /// Creates a statement counter expr, or nothing if the statement is outside of the changed lines.
+        #[tracing::instrument(skip(self, span), fields(stmt_id))]
+        fn create_stmt_increase_counter_expr(&mut self, span: &Span) -> Option<Expr> {
+            let stmt_range = self.lookup_range(span);
 
-            let stmt_id = self.cov.borrow_mut().new_statement(&stmt_range);
+            let stmt_id = self.cov.borrow_mut().try_new_statement(&stmt_range)?;
 
             tracing::Span::current().record("stmt_id", &stmt_id);
 
-            crate::create_increase_counter_expr(
-                &crate::constants::idents::IDENT_S,
-                stmt_id,
+            Some(self.counter_codegen.statement_increment(
                 &self.cov_fn_ident,
-                idx,
-            )
+                &self.counter_idents.s,
+                stmt_id,
+            ))
         }
 
         // Mark to prepend statement increase counter to current stmt.
@@ -155,14 +203,20 @@ macro_rules! instrumentation_counter_helper {
         // }
         #[tracing::instrument(skip_all)]
         fn mark_prepend_stmt_counter(&mut self, span: &Span) {
-            let increment_expr = self.create_stmt_increase_counter_expr(span, None);
-            self.before.push(Stmt::Expr(ExprStmt {
-                span: swc_core::common::DUMMY_SP,
-                expr: Box::new(increment_expr),
-            }));
+            if let Some(increment_expr) = self.create_stmt_increase_counter_expr(span) {
+                self.before.push(Stmt::Expr(ExprStmt {
+                    span: swc_core::common::DUMMY_SP,
+                    expr: Box::new(increment_expr),
+                }));
+            }
         }
 
         /// Common logics for the fn-like visitors to insert fn instrumentation counters.
+        ///
+        /// The fn counter is the first statement of the body, while default-arg branch counters
+        /// are wrapped into the parameter initializers. Parameters are bound before the body runs,
+        /// so default-arg branches are recorded first, and both are recorded even if the body
+        /// throws immediately. If a default initializer throws, the fn counter is not increased.
         #[tracing::instrument(skip_all)]
         fn create_fn_instrumentation(&mut self, ident: &Option<&Ident>, function: &mut Function) {
             let (span, name) = if let Some(ident) = &ident {
@@ -171,58 +225,124 @@ macro_rules! instrumentation_counter_helper {
                 (&function.span, None)
             };
 
-            let range = crate::lookup_range::get_range_from_span(&self.source_map, span);
-            let body_span = if let Some(body) = &function.body {
-                body.span
-            } else {
-                // TODO: probably this should never occur
-                function.span
+            self.create_fn_body_instrumentation(span, name, function.body.as_mut());
+        }
+
+        /// Same as `create_fn_instrumentation`, for the class constructor which is not a `Function`.
+        #[tracing::instrument(skip_all)]
+        fn create_constructor_instrumentation(&mut self, constructor: &mut Constructor) {
+            let span = match &constructor.key {
+                PropName::Ident(ident) => ident.span,
+                _ => constructor.span,
+            };
+
+            self.create_fn_body_instrumentation(
+                &span,
+                Some("constructor".to_string()),
+                constructor.body.as_mut(),
+            );
+        }
+
+        /// Registers a fn counter and inserts it at the top of the body.
+        fn create_fn_body_instrumentation(
+            &mut self,
+            span: &Span,
+            name: Option<String>,
+            body: Option<&mut BlockStmt>,
+        ) {
+            let blockstmt = match body {
+                Some(body) => body,
+                // i.e TypeScript overload signatures, erased by the compilation and nothing to count.
+                // Not a skipped node either, as there is no code left to cover.
+                None => return,
             };
 
-            let body_range = crate::lookup_range::get_range_from_span(&self.source_map, &body_span);
+            let range = self.lookup_range(span);
+
+            let body_range = self.lookup_range(&blockstmt.span);
             let index = self
                 .cov
                 .borrow_mut()
-                .new_function(&name, &range, &body_range);
+                .try_new_function(&name, &range, &body_range);
+            let index = match index {
+                Some(index) => index,
+                None => return,
+            };
 
-            match &mut function.body {
-                Some(blockstmt) => {
-                    let b = crate::create_increase_counter_expr(
-                        &crate::constants::idents::IDENT_F,
-                        index,
-                        &self.cov_fn_ident,
-                        None,
-                    );
-                    let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
+            let b = self.create_fn_increase_counter_expr(index);
+            let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
+                span: swc_core::common::DUMMY_SP,
+                expr: Box::new(b),
+            })];
+            prepended_vec.extend(blockstmt.stmts.take());
+            blockstmt.stmts = prepended_vec;
+        }
+
+        /// Register a fn counter for the arrow fn, returns the counter stmt to be inserted at the
+        /// top of its body. Returns nothing if `instrument_arrow_functions` is disabled or the arrow fn
+        /// is outside of the changed lines, body statements are still instrumented.
+        fn create_arrow_fn_instrumentation(&mut self, span: &Span, body_span: &Span) -> Vec<Stmt> {
+            if !self.instrument_options.instrument_arrow_functions {
+                return vec![];
+            }
+
+            let range = self.lookup_range(span);
+            let body_range = self.lookup_range(body_span);
+            let index = self
+                .cov
+                .borrow_mut()
+                .try_new_function(&None, &range, &body_range);
+
+            index
+                .map(|index| {
+                    Stmt::Expr(ExprStmt {
                         span: swc_core::common::DUMMY_SP,
-                        expr: Box::new(b),
-                    })];
-                    prepended_vec.extend(blockstmt.stmts.take());
-                    blockstmt.stmts = prepended_vec;
-                }
-                _ => {
-                    unimplemented!("Unable to process function body node type")
+                        expr: Box::new(self.create_fn_increase_counter_expr(index)),
+                    })
+                })
+                .into_iter()
+                .collect()
+        }
+
+        /// Creates a function counter expr via the counter codegen.
+        fn create_fn_increase_counter_expr(&self, index: u32) -> Expr {
+            self.counter_codegen.function_increment(
+                &self.cov_fn_ident,
+                &self.counter_idents.f,
+                index,
+            )
+        }
+
+        fn is_injected_counter_expr(&self, expr: &Expr) -> bool {
+            match expr {
+                // Batched counters, i.e `optimize_straight_line_blocks`
+                Expr::Seq(SeqExpr { exprs, .. }) => {
+                    !exprs.is_empty()
+                        && exprs.iter().all(|expr| self.is_injected_counter_expr(expr))
                 }
+                _ => self
+                    .counter_codegen
+                    .is_counter_expr(&self.cov_fn_ident, expr),
             }
         }
 
-        fn is_injected_counter_expr(&self, expr: &Expr) -> bool {
-            if let Expr::Update(UpdateExpr { arg, .. }) = expr {
-                if let Expr::Member(MemberExpr { obj, .. }) = &**arg {
-                    if let Expr::Member(MemberExpr { obj, .. }) = &**obj {
-                        if let Expr::Call(CallExpr { callee, .. }) = &**obj {
-                            if let Callee::Expr(expr) = callee {
-                                if let Expr::Ident(ident) = &**expr {
-                                    if ident == &self.cov_fn_ident {
-                                        return true;
-                                    }
-                                }
-                            }
-                        }
+        /// Determine if given stmt is the coverage fn declaration or its initialization call
+        /// injected by the instrumentation. Those should never be instrumented themselves.
+        fn is_injected_coverage_template(&self, stmt: &Stmt) -> bool {
+            match stmt {
+                Stmt::Decl(Decl::Fn(fn_decl)) => fn_decl.ident.sym == self.cov_fn_ident.sym,
+                Stmt::Expr(ExprStmt { expr, .. }) => match &**expr {
+                    Expr::Call(CallExpr {
+                        callee: Callee::Expr(callee),
+                        args,
+                        ..
+                    }) if args.is_empty() => {
+                        matches!(&**callee, Expr::Ident(ident) if ident.sym == self.cov_fn_ident.sym)
                     }
-                }
-            };
-            false
+                    _ => false,
+                },
+                _ => false,
+            }
         }
 
         /// Determine if given stmt is an injected counter by transform.
@@ -235,7 +355,15 @@ macro_rules! instrumentation_counter_helper {
         }
 
         fn cover_statement(&mut self, expr: &mut Expr) {
-            let span = expr.span();
+            let span = match expr {
+                // Decorated class expr's range starts at `class`, not the decorators.
+                Expr::Class(ClassExpr { class, .. }) => crate::lookup_range::skip_decorators(
+                    &self.source_map,
+                    &class.span,
+                    &class.decorators,
+                ),
+                _ => expr.span(),
+            };
             // This is ugly, poor man's substitute to istanbul's `insertCounter` to determine
             // when to replace givn expr to wrapped Paren or prepend stmt counter.
             // We can't do insert parent node's sibling in downstream's child node.
@@ -298,5 +426,16 @@ macro_rules! instrumentation_counter_helper {
                 self.replace_expr_with_stmt_counter(expr);
             }
         }
+
+        /// Common logics for the class property-like visitors to cover the property value.
+        /// Property values are evaluated per instance, wrap the value itself
+        /// instead of prepending the counter to the class declaration.
+        fn cover_class_prop_value(&mut self, value: &mut Option<Box<Expr>>) {
+            if let Some(value) = value {
+                self.replace_expr_with_stmt_counter(&mut **value);
+                // Visit the value to register fn counters (i.e arrow fn values) and branches.
+                value.visit_mut_with(self);
+            }
+        }
     };
 }
@@ -6,9 +6,21 @@ macro_rules! instrumentation_stmt_counter_helper {
         #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
         fn insert_stmts_counter(&mut self, stmts: &mut Vec<Stmt>) {
             let mut new_stmts = vec![];
+            let mut has_preceding_stmt = false;
 
             for mut stmt in stmts.drain(..) {
-                if !self.is_injected_counter_stmt(&stmt) {
+                // Empty statements (`;`) and type-only declarations erased at runtime never get a counter.
+                let is_type_only = match &stmt {
+                    Stmt::Decl(Decl::TsTypeAlias(_)) | Stmt::Decl(Decl::TsInterface(_)) => true,
+                    Stmt::Decl(Decl::TsEnum(ts_enum)) => ts_enum.is_const || ts_enum.declare,
+                    _ => false,
+                };
+                if !self.is_injected_counter_stmt(&stmt)
+                    && !self.is_injected_coverage_template(&stmt)
+                    && !matches!(stmt, Stmt::Empty(_))
+                    && !is_type_only
+                    && !self.is_skipped_trivial_stmt(&stmt, has_preceding_stmt)
+                {
                     let (old, ignore_current) = self.on_enter(&mut stmt);
 
                     match ignore_current {
@@ -18,22 +30,142 @@ macro_rules! instrumentation_stmt_counter_helper {
                                 self.source_map.clone(),
                                 self.comments.clone(),
                                 self.cov.clone(),
+                                self.cov_fn_ident.clone(),
+                                self.cov_fn_temp_ident.clone(),
                                 self.instrument_options.clone(),
                                 self.nodes.clone(),
                                 ignore_current,
+                                self.counter_codegen.clone(),
+                                self.lookup_cache.clone(),
                             );
                             stmt.visit_mut_children_with(&mut visitor);
 
-                            new_stmts.extend(visitor.before.drain(..));
+                            new_stmts.extend(
+                                self.coalesce_counter_stmts(visitor.before.drain(..).collect()),
+                            );
                         }
                     }
                     self.on_exit(old);
                 }
 
                 new_stmts.push(stmt);
+                has_preceding_stmt = true;
+            }
+
+            if self.instrument_options.optimize_straight_line_blocks {
+                new_stmts = self.batch_straight_line_counters(new_stmts);
             }
 
             *stmts = new_stmts;
         }
+
+        /// Determine if given stmt should not have a counter by `skip_trivial_statements`.
+        fn is_skipped_trivial_stmt(&self, stmt: &Stmt, has_preceding_stmt: bool) -> bool {
+            self.instrument_options.skip_trivial_statements
+                && crate::trivial_stmt::is_trivial_stmt(stmt, has_preceding_stmt)
+        }
+
+        /// Merge adjacent counter stmts prepended to a statement into a single sequence expr,
+        /// i.e `cov().s[0]++, cov().s[1]++;` if `coalesce_counters` is enabled.
+        /// Counters are still increased in the same order, only reduces the number of statements.
+        fn coalesce_counter_stmts(&self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+            if !self.instrument_options.coalesce_counters {
+                return stmts;
+            }
+
+            fn flush(new_stmts: &mut Vec<Stmt>, counters: &mut Vec<Box<Expr>>) {
+                let expr = match counters.len() {
+                    0 => return,
+                    1 => counters.remove(0),
+                    _ => Box::new(Expr::Seq(SeqExpr {
+                        span: swc_core::common::DUMMY_SP,
+                        exprs: counters.drain(..).collect(),
+                    })),
+                };
+                new_stmts.push(Stmt::Expr(ExprStmt {
+                    span: swc_core::common::DUMMY_SP,
+                    expr,
+                }));
+            }
+
+            let mut new_stmts = vec![];
+            let mut counters = vec![];
+            for stmt in stmts {
+                match stmt {
+                    Stmt::Expr(ExprStmt { expr, .. }) if self.is_injected_counter_expr(&expr) => {
+                        counters.push(expr);
+                    }
+                    stmt => {
+                        flush(&mut new_stmts, &mut counters);
+                        new_stmts.push(stmt);
+                    }
+                }
+            }
+            flush(&mut new_stmts, &mut counters);
+
+            new_stmts
+        }
+
+        /// Merge stmt counters of the consecutive statements unlikely to interrupt execution
+        /// into a single sequence expr at the entry, i.e `cov().s[0]++, cov().s[1]++;`.
+        /// If one of them throws anyway, counters of the following statements are over-reported.
+        /// The last statement of the run can be anything, as its counter is increased
+        /// before it runs regardless.
+        fn batch_straight_line_counters(&self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+            let mut new_stmts = vec![];
+            let mut stmts = stmts.into_iter().peekable();
+
+            while let Some(stmt) = stmts.next() {
+                if !self.is_injected_counter_stmt(&stmt) {
+                    new_stmts.push(stmt);
+                    continue;
+                }
+
+                let mut counters = vec![stmt];
+                let mut body = vec![];
+                while let Some(stmt) = stmts.next() {
+                    let is_straight_line = match &stmt {
+                        Stmt::Expr(ExprStmt { expr, .. }) => {
+                            let mut finder = crate::visitors::finders::InterruptionFinder::new();
+                            expr.visit_with(&mut finder);
+                            !finder.0
+                        }
+                        _ => false,
+                    };
+                    body.push(stmt);
+
+                    if !is_straight_line
+                        || !stmts
+                            .peek()
+                            .map_or(false, |next| self.is_injected_counter_stmt(next))
+                    {
+                        break;
+                    }
+                    counters.push(stmts.next().expect("Counter should exist"));
+                }
+
+                if counters.len() > 1 {
+                    let exprs = counters
+                        .into_iter()
+                        .filter_map(|counter| match counter {
+                            Stmt::Expr(ExprStmt { expr, .. }) => Some(expr),
+                            _ => None,
+                        })
+                        .collect();
+                    new_stmts.push(Stmt::Expr(ExprStmt {
+                        span: swc_core::common::DUMMY_SP,
+                        expr: Box::new(Expr::Seq(SeqExpr {
+                            span: swc_core::common::DUMMY_SP,
+                            exprs,
+                        })),
+                    }));
+                } else {
+                    new_stmts.extend(counters);
+                }
+                new_stmts.extend(body);
+            }
+
+            new_stmts
+        }
     };
 }
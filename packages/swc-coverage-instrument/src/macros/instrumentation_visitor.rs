@@ -42,60 +42,18 @@ macro_rules! instrumentation_visitor {
                 Some(crate::hint_comments::IgnoreScope::Next) => {}
                 _ => match &mut *arrow_expr.body {
                     BlockStmtOrExpr::BlockStmt(block_stmt) => {
-                        let range = crate::lookup_range::get_range_from_span(
-                            &self.source_map,
-                            &arrow_expr.span,
-                        );
-                        let body_range = crate::lookup_range::get_range_from_span(
-                            &self.source_map,
-                            &block_stmt.span,
-                        );
-                        let index = self
-                            .cov
-                            .borrow_mut()
-                            .new_function(&None, &range, &body_range);
-                        let b = crate::create_increase_counter_expr(
-                            &crate::constants::idents::IDENT_F,
-                            index,
-                            &self.cov_fn_ident,
-                            None,
-                        );
-
-                        // insert fn counter expression
-                        let mut new_stmts = vec![Stmt::Expr(ExprStmt {
-                            span: swc_core::common::DUMMY_SP,
-                            expr: Box::new(b),
-                        })];
+                        let fn_counter = self
+                            .create_arrow_fn_instrumentation(&arrow_expr.span, &block_stmt.span);
                         // if arrow fn body is already blockstmt, insert stmt counter for each
                         self.insert_stmts_counter(&mut block_stmt.stmts);
-                        new_stmts.extend(block_stmt.stmts.drain(..));
-                        block_stmt.stmts = new_stmts;
+                        // Put the fn counter in front of the existing body in place.
+                        block_stmt.stmts.splice(0..0, fn_counter);
                     }
                     BlockStmtOrExpr::Expr(expr) => {
                         // TODO: refactor common logics creates a blockstmt from single expr
-                        let range = crate::lookup_range::get_range_from_span(
-                            &self.source_map,
-                            &arrow_expr.span,
-                        );
                         let span = expr.span();
-                        let body_range =
-                            crate::lookup_range::get_range_from_span(&self.source_map, &span);
-                        let index = self
-                            .cov
-                            .borrow_mut()
-                            .new_function(&None, &range, &body_range);
-                        let b = crate::create_increase_counter_expr(
-                            &crate::constants::idents::IDENT_F,
-                            index,
-                            &self.cov_fn_ident,
-                            None,
-                        );
-
-                        // insert fn counter expression
-                        let mut stmts = vec![Stmt::Expr(ExprStmt {
-                            span: swc_core::common::DUMMY_SP,
-                            expr: Box::new(b),
-                        })];
+                        let mut stmts =
+                            self.create_arrow_fn_instrumentation(&arrow_expr.span, &span);
 
                         // single line expr in arrow fn need to be converted into return stmt
                         // Note we should preserve original expr's span, otherwise statementmap will lose correct
@@ -128,7 +86,7 @@ macro_rules! instrumentation_visitor {
             if !self.is_injected_counter_stmt(stmt) {
                 let span = crate::lookup_range::get_stmt_span(&stmt);
                 if let Some(span) = span {
-                    let increment_expr = self.create_stmt_increase_counter_expr(span, None);
+                    let increment_expr = self.create_stmt_increase_counter_expr(span);
 
                     self.before.push(Stmt::Expr(ExprStmt {
                         span: DUMMY_SP,
@@ -228,45 +186,76 @@ macro_rules! instrumentation_visitor {
             match ignore_current {
                 Some(crate::hint_comments::IgnoreScope::Next) => {}
                 _ => {
-                    let range =
-                        crate::lookup_range::get_range_from_span(&self.source_map, &cond_expr.span);
-                    let branch = self.cov.borrow_mut().new_branch(
+                    let range = self.lookup_range(&cond_expr.span);
+                    let branch = self.cov.borrow_mut().try_new_branch(
                         istanbul_oxide::BranchType::CondExpr,
                         &range,
                         false,
                     );
 
-                    let c_hint = crate::hint_comments::lookup_hint_comments(
-                        &self.comments,
-                        Some(&cond_expr.cons.span()),
-                    );
-                    let a_hint = crate::hint_comments::lookup_hint_comments(
-                        &self.comments,
-                        Some(&cond_expr.alt.span()),
-                    );
+                    if let Some(branch) = branch {
+                        let ignore_cons = crate::hint_comments::should_ignore_branch_path(
+                            &self.comments,
+                            &cond_expr.cons.span(),
+                        );
+                        let ignore_alt = crate::hint_comments::should_ignore_branch_path(
+                            &self.comments,
+                            &cond_expr.alt.span(),
+                        );
 
-                    if c_hint.as_deref() != Some("next") {
-                        // TODO: do we need this?
-                        // cond_expr.cons.visit_mut_children_with(self);
+                        if !ignore_cons {
+                            // TODO: do we need this?
+                            // cond_expr.cons.visit_mut_children_with(self);
 
-                        // replace consequence to the paren for increase expr + expr itself
-                        self.replace_expr_with_branch_counter(&mut *cond_expr.cons, branch);
-                    }
+                            // replace consequence to the paren for increase expr + expr itself
+                            let range = self.lookup_range(&cond_expr.cons.span());
+                            self.replace_expr_with_branch_counter(
+                                &mut *cond_expr.cons,
+                                branch,
+                                &range,
+                            );
+                        }
 
-                    if a_hint.as_deref() != Some("next") {
-                        // TODO: do we need this?
-                        // cond_expr.alt.visit_mut_children_with(self);
+                        if !ignore_alt {
+                            // TODO: do we need this?
+                            // cond_expr.alt.visit_mut_children_with(self);
 
-                        // replace consequence to the paren for increase expr + expr itself
-                        self.replace_expr_with_branch_counter(&mut *cond_expr.alt, branch);
+                            // replace consequence to the paren for increase expr + expr itself
+                            let range = self.lookup_range(&cond_expr.alt.span());
+                            self.replace_expr_with_branch_counter(
+                                &mut *cond_expr.alt,
+                                branch,
+                                &range,
+                            );
+                        }
                     }
                 }
             };
 
+            // Nested ternary in the alternate (`a ? 1 : b ? 2 : 3`) is wrapped as a whole above,
+            // then registers its own branch once here when visiting the wrapped children.
             cond_expr.visit_mut_children_with(self);
             self.on_exit(old);
         }
 
+        // SequenceExpression: visit each expression, skipping counters injected by the
+        // transform itself (i.e `(cov().b[0][0]++, expr)` created by replace_expr_with_counter).
+        #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
+        fn visit_mut_seq_expr(&mut self, seq_expr: &mut SeqExpr) {
+            let (old, ignore_current) = self.on_enter(seq_expr);
+            match ignore_current {
+                Some(crate::hint_comments::IgnoreScope::Next) => {}
+                _ => {
+                    for expr in seq_expr.exprs.iter_mut() {
+                        if !self.is_injected_counter_expr(&**expr) {
+                            expr.visit_mut_with(self);
+                        }
+                    }
+                }
+            }
+            self.on_exit(old);
+        }
+
         // ReturnStatement: entries(coverStatement),
         #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
         fn visit_mut_return_stmt(&mut self, return_stmt: &mut ReturnStmt) {
@@ -291,6 +280,14 @@ macro_rules! instrumentation_visitor {
             self.on_exit(old);
         }
 
+        // `using` / `await using` declarations, declarators are covered same as `var`.
+        #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
+        fn visit_mut_using_decl(&mut self, using_decl: &mut UsingDecl) {
+            let (old, _ignore_current) = self.on_enter(using_decl);
+            using_decl.visit_mut_children_with(self);
+            self.on_exit(old);
+        }
+
         // ClassDeclaration: entries(parenthesizedExpressionProp('superClass')),
         #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
         fn visit_mut_class_decl(&mut self, class_decl: &mut ClassDecl) {
@@ -298,7 +295,11 @@ macro_rules! instrumentation_visitor {
             match ignore_current {
                 Some(crate::hint_comments::IgnoreScope::Next) => {}
                 _ => {
-                    //self.mark_prepend_stmt_counter(&class_decl.class.span);
+                    // Same as istanbul, class declaration itself does not have a stmt counter.
+                    // Static property values / static blocks are wrapped in place, so their
+                    // counters increase once at definition time in source order.
+                    // Super class expr (`class C extends (a ? B : D) {}`) is visited as a child
+                    // to register its own branches, its parens are preserved as-is.
                     class_decl.visit_mut_children_with(self);
                 }
             }
@@ -306,17 +307,34 @@ macro_rules! instrumentation_visitor {
             self.on_exit(old);
         }
 
+        // Regular enums are emitted as runtime objects, counted as a statement same as a var decl.
+        // `const enum` is inlined into its usages and `declare enum` is ambient, both are erased
+        // at compile time so they must not have any counter including their member initializers.
+        #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
+        fn visit_mut_ts_enum_decl(&mut self, ts_enum_decl: &mut TsEnumDecl) {
+            if ts_enum_decl.is_const || ts_enum_decl.declare {
+                return;
+            }
+
+            let (old, ignore_current) = self.on_enter(ts_enum_decl);
+            match ignore_current {
+                Some(crate::hint_comments::IgnoreScope::Next) => {}
+                _ => {
+                    self.mark_prepend_stmt_counter(&ts_enum_decl.span);
+                    ts_enum_decl.visit_mut_children_with(self);
+                }
+            }
+
+            self.on_exit(old);
+        }
+
         // ClassProperty: entries(coverClassPropDeclarator),
         #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
         fn visit_mut_class_prop(&mut self, class_prop: &mut ClassProp) {
             let (old, ignore_current) = self.on_enter(class_prop);
             match ignore_current {
                 Some(crate::hint_comments::IgnoreScope::Next) => {}
-                _ => {
-                    if let Some(value) = &mut class_prop.value {
-                        self.cover_statement(&mut *value);
-                    }
-                }
+                _ => self.cover_class_prop_value(&mut class_prop.value),
             }
             self.on_exit(old);
         }
@@ -324,15 +342,23 @@ macro_rules! instrumentation_visitor {
         // ClassPrivateProperty: entries(coverClassPropDeclarator),
         #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
         fn visit_mut_private_prop(&mut self, private_prop: &mut PrivateProp) {
-            // TODO: this is same as visit_mut_class_prop
             let (old, ignore_current) = self.on_enter(private_prop);
             match ignore_current {
                 Some(crate::hint_comments::IgnoreScope::Next) => {}
-                _ => {
-                    if let Some(value) = &mut private_prop.value {
-                        self.cover_statement(&mut *value);
-                    }
-                }
+                _ => self.cover_class_prop_value(&mut private_prop.value),
+            }
+            self.on_exit(old);
+        }
+
+        // ClassAccessorProperty: entries(coverClassPropDeclarator),
+        #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
+        fn visit_mut_auto_accessor(&mut self, auto_accessor: &mut AutoAccessor) {
+            let (old, ignore_current) = self.on_enter(auto_accessor);
+            match ignore_current {
+                Some(crate::hint_comments::IgnoreScope::Next) => {}
+                // The getter / setter pair is generated by the downlevel transform, not present
+                // in the AST: only the initializer needs a counter, same as class properties.
+                _ => self.cover_class_prop_value(&mut auto_accessor.value),
             }
             self.on_exit(old);
         }
@@ -360,6 +386,35 @@ macro_rules! instrumentation_visitor {
                             );
                             class_method.visit_mut_children_with(self);
                         }
+                    } else {
+                        let range = self.lookup_range(&class_method.span);
+                        self.cov
+                            .borrow_mut()
+                            .add_skipped_node("class method with non-identifier key", &range);
+                    }
+                }
+            }
+            self.on_exit(old);
+        }
+
+        // ClassMethod (kind: constructor): entries(coverFunction),
+        #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
+        fn visit_mut_constructor(&mut self, constructor: &mut Constructor) {
+            let (old, ignore_current) = self.on_enter(constructor);
+            match ignore_current {
+                Some(crate::hint_comments::IgnoreScope::Next) => {}
+                _ => {
+                    let should_ignore_via_options = self
+                        .instrument_options
+                        .ignore_class_methods
+                        .iter()
+                        .any(|v| v.as_str() == "constructor");
+
+                    if !should_ignore_via_options {
+                        self.create_constructor_instrumentation(constructor);
+                        // Visits params to register default-arg branches, including the defaults
+                        // of TypeScript parameter properties (`constructor(private x = 1)`).
+                        constructor.visit_mut_children_with(self);
                     }
                 }
             }
@@ -422,31 +477,25 @@ macro_rules! instrumentation_visitor {
                         if !should_ignore_via_options {
                             let (span, name) = (&ident.span, Some(ident.sym.to_string()));
 
-                            let range =
-                                crate::lookup_range::get_range_from_span(&self.source_map, span);
+                            let range = self.lookup_range(span);
                             if let Some(body) = &mut getter_prop.body {
                                 let body_span = body.span;
-                                let body_range = crate::lookup_range::get_range_from_span(
-                                    &self.source_map,
-                                    &body_span,
+                                let body_range = self.lookup_range(&body_span);
+                                let index = self.cov.borrow_mut().try_new_function(
+                                    &name,
+                                    &range,
+                                    &body_range,
                                 );
-                                let index =
-                                    self.cov
-                                        .borrow_mut()
-                                        .new_function(&name, &range, &body_range);
-
-                                let b = crate::create_increase_counter_expr(
-                                    &crate::constants::idents::IDENT_F,
-                                    index,
-                                    &self.cov_fn_ident,
-                                    None,
-                                );
-                                let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
-                                    span: swc_core::common::DUMMY_SP,
-                                    expr: Box::new(b),
-                                })];
-                                prepended_vec.extend(body.stmts.take());
-                                body.stmts = prepended_vec;
+
+                                if let Some(index) = index {
+                                    let b = self.create_fn_increase_counter_expr(index);
+                                    let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
+                                        span: swc_core::common::DUMMY_SP,
+                                        expr: Box::new(b),
+                                    })];
+                                    prepended_vec.extend(body.stmts.take());
+                                    body.stmts = prepended_vec;
+                                }
                             }
                             getter_prop.visit_mut_children_with(self);
                         }
@@ -454,31 +503,24 @@ macro_rules! instrumentation_visitor {
                         let span = &getter_prop.span;
                         let name: Option<String> = Some("anonymous".to_owned());
 
-                        let range =
-                            crate::lookup_range::get_range_from_span(&self.source_map, span);
+                        let range = self.lookup_range(span);
                         if let Some(body) = &mut getter_prop.body {
                             let body_span = body.span;
-                            let body_range = crate::lookup_range::get_range_from_span(
-                                &self.source_map,
-                                &body_span,
-                            );
+                            let body_range = self.lookup_range(&body_span);
                             let index =
                                 self.cov
                                     .borrow_mut()
-                                    .new_function(&name, &range, &body_range);
+                                    .try_new_function(&name, &range, &body_range);
 
-                            let b = crate::create_increase_counter_expr(
-                                &crate::constants::idents::IDENT_F,
-                                index,
-                                &self.cov_fn_ident,
-                                None,
-                            );
-                            let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
-                                span: swc_core::common::DUMMY_SP,
-                                expr: Box::new(b),
-                            })];
-                            prepended_vec.extend(body.stmts.take());
-                            body.stmts = prepended_vec;
+                            if let Some(index) = index {
+                                let b = self.create_fn_increase_counter_expr(index);
+                                let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
+                                    span: swc_core::common::DUMMY_SP,
+                                    expr: Box::new(b),
+                                })];
+                                prepended_vec.extend(body.stmts.take());
+                                body.stmts = prepended_vec;
+                            }
                         }
                         getter_prop.visit_mut_children_with(self);
                     }
@@ -508,31 +550,25 @@ macro_rules! instrumentation_visitor {
                         if !should_ignore_via_options {
                             let (span, name) = (&ident.span, Some(ident.sym.to_string()));
 
-                            let range =
-                                crate::lookup_range::get_range_from_span(&self.source_map, span);
+                            let range = self.lookup_range(span);
                             if let Some(body) = &mut setter_prop.body {
                                 let body_span = body.span;
-                                let body_range = crate::lookup_range::get_range_from_span(
-                                    &self.source_map,
-                                    &body_span,
+                                let body_range = self.lookup_range(&body_span);
+                                let index = self.cov.borrow_mut().try_new_function(
+                                    &name,
+                                    &range,
+                                    &body_range,
                                 );
-                                let index =
-                                    self.cov
-                                        .borrow_mut()
-                                        .new_function(&name, &range, &body_range);
-
-                                let b = crate::create_increase_counter_expr(
-                                    &crate::constants::idents::IDENT_F,
-                                    index,
-                                    &self.cov_fn_ident,
-                                    None,
-                                );
-                                let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
-                                    span: swc_core::common::DUMMY_SP,
-                                    expr: Box::new(b),
-                                })];
-                                prepended_vec.extend(body.stmts.take());
-                                body.stmts = prepended_vec;
+
+                                if let Some(index) = index {
+                                    let b = self.create_fn_increase_counter_expr(index);
+                                    let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
+                                        span: swc_core::common::DUMMY_SP,
+                                        expr: Box::new(b),
+                                    })];
+                                    prepended_vec.extend(body.stmts.take());
+                                    body.stmts = prepended_vec;
+                                }
                             }
                             setter_prop.visit_mut_children_with(self);
                         }
@@ -540,31 +576,24 @@ macro_rules! instrumentation_visitor {
                         let span = &setter_prop.span;
                         let name: Option<String> = Some("anonymous".to_owned());
 
-                        let range =
-                            crate::lookup_range::get_range_from_span(&self.source_map, span);
+                        let range = self.lookup_range(span);
                         if let Some(body) = &mut setter_prop.body {
                             let body_span = body.span;
-                            let body_range = crate::lookup_range::get_range_from_span(
-                                &self.source_map,
-                                &body_span,
-                            );
+                            let body_range = self.lookup_range(&body_span);
                             let index =
                                 self.cov
                                     .borrow_mut()
-                                    .new_function(&name, &range, &body_range);
+                                    .try_new_function(&name, &range, &body_range);
 
-                            let b = crate::create_increase_counter_expr(
-                                &crate::constants::idents::IDENT_F,
-                                index,
-                                &self.cov_fn_ident,
-                                None,
-                            );
-                            let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
-                                span: swc_core::common::DUMMY_SP,
-                                expr: Box::new(b),
-                            })];
-                            prepended_vec.extend(body.stmts.take());
-                            body.stmts = prepended_vec;
+                            if let Some(index) = index {
+                                let b = self.create_fn_increase_counter_expr(index);
+                                let mut prepended_vec = vec![Stmt::Expr(ExprStmt {
+                                    span: swc_core::common::DUMMY_SP,
+                                    expr: Box::new(b),
+                                })];
+                                prepended_vec.extend(body.stmts.take());
+                                body.stmts = prepended_vec;
+                            }
                         }
                         setter_prop.visit_mut_children_with(self);
                     }
@@ -668,28 +697,38 @@ macro_rules! instrumentation_visitor {
                     // Insert stmt counter for `switch` itself, then create a new branch
                     self.mark_prepend_stmt_counter(&switch_stmt.span);
 
-                    let range = crate::lookup_range::get_range_from_span(
-                        &self.source_map,
-                        &switch_stmt.span,
-                    );
-                    let branch =
-                        self.cov
-                            .borrow_mut()
-                            .new_branch(crate::BranchType::Switch, &range, false);
-
-                    // traverse `case` with a visitor contains branch idx, insert new
-                    // branch increase counter accordingly
-                    let mut visitor = crate::visitors::switch_case_visitor::SwitchCaseVisitor::new(
-                        self.source_map.clone(),
-                        self.comments.clone(),
-                        self.cov.clone(),
-                        self.instrument_options.clone(),
-                        self.nodes.clone(),
-                        ignore_current,
-                        branch,
+                    let range = self.lookup_range(&switch_stmt.span);
+                    let branch = self.cov.borrow_mut().try_new_branch(
+                        crate::BranchType::Switch,
+                        &range,
+                        false,
                     );
 
-                    switch_stmt.visit_mut_children_with(&mut visitor);
+                    if let Some(branch) = branch {
+                        // traverse `case` with a visitor contains branch idx, insert new
+                        // branch increase counter accordingly. The discriminant is visited by
+                        // the same visitor, registers its own branches (i.e `switch (a ? b : c)`) after
+                        // the switch branch.
+                        let mut visitor =
+                            crate::visitors::switch_case_visitor::SwitchCaseVisitor::new(
+                                self.source_map.clone(),
+                                self.comments.clone(),
+                                self.cov.clone(),
+                                self.cov_fn_ident.clone(),
+                                self.cov_fn_temp_ident.clone(),
+                                self.instrument_options.clone(),
+                                self.nodes.clone(),
+                                ignore_current,
+                                self.counter_codegen.clone(),
+                                self.lookup_cache.clone(),
+                                branch,
+                            );
+
+                        switch_stmt.visit_mut_children_with(&mut visitor);
+                    } else {
+                        // Cases are outside of the changed lines, still visit them for the inner counters.
+                        switch_stmt.visit_mut_children_with(self);
+                    }
                 }
             }
             self.on_exit(old);
@@ -708,51 +747,65 @@ macro_rules! instrumentation_visitor {
                     // cover_statement's is_stmt prepend logic for individual child stmt visitor
                     self.mark_prepend_stmt_counter(&if_stmt.span);
 
-                    let range =
-                        crate::lookup_range::get_range_from_span(&self.source_map, &if_stmt.span);
+                    let range = self.lookup_range(&if_stmt.span);
                     let branch =
                         self.cov
                             .borrow_mut()
-                            .new_branch(crate::BranchType::If, &range, false);
+                            .try_new_branch(crate::BranchType::If, &range, false);
+
+                    // `wrap_with_counter` borrows `self` mutably until its last call,
+                    // the implicit else path registered in between can't access `self`.
+                    let implicit_else_branch = self.instrument_options.implicit_else_branch;
+                    let cov = self.cov.clone();
 
-                    let mut wrap_with_counter = |stmt: &mut Box<Stmt>| {
+                    let mut wrap_with_counter = |stmt: &mut Box<Stmt>, add_branch_path: bool| {
                         let mut stmt_body = *stmt.take();
 
-                        // create a branch path counter
-                        let idx = self.cov.borrow_mut().add_branch_path(branch, &range);
-                        let expr = crate::create_increase_counter_expr(
-                            &crate::constants::idents::IDENT_B,
-                            branch,
-                            &self.cov_fn_ident,
-                            Some(idx),
-                        );
+                        // create a branch path counter, if the branch is within the changed lines
+                        let expr = if let (Some(branch), true) = (branch, add_branch_path) {
+                            let idx = self.cov.borrow_mut().add_branch_path(branch, &range);
+                            let expr = self.counter_codegen.branch_increment(
+                                &self.cov_fn_ident,
+                                &self.counter_idents.b,
+                                branch,
+                                idx,
+                            );
 
-                        let expr = Stmt::Expr(ExprStmt {
-                            span: swc_core::common::DUMMY_SP,
-                            expr: Box::new(expr),
-                        });
+                            Some(Stmt::Expr(ExprStmt {
+                                span: swc_core::common::DUMMY_SP,
+                                expr: Box::new(expr),
+                            }))
+                        } else {
+                            None
+                        };
 
                         let body = if let Stmt::Block(mut block_stmt) = stmt_body {
                             // if cons / alt is already blockstmt, insert stmt counter for each
                             self.insert_stmts_counter(&mut block_stmt.stmts);
 
-                            let mut new_stmts = vec![expr];
+                            let mut new_stmts: Vec<Stmt> = expr.into_iter().collect();
                             new_stmts.extend(block_stmt.stmts.drain(..));
 
                             block_stmt.stmts = new_stmts;
                             block_stmt
                         } else {
-                            let mut stmts = vec![expr];
+                            let mut stmts: Vec<Stmt> = expr.into_iter().collect();
                             let mut visitor = crate::visitors::stmt_like_visitor::StmtVisitor::new(
                                 self.source_map.clone(),
                                 self.comments.clone(),
                                 self.cov.clone(),
+                                self.cov_fn_ident.clone(),
+                                self.cov_fn_temp_ident.clone(),
                                 self.instrument_options.clone(),
                                 self.nodes.clone(),
                                 ignore_current,
+                                self.counter_codegen.clone(),
+                                self.lookup_cache.clone(),
                             );
                             stmt_body.visit_mut_with(&mut visitor);
-                            stmts.extend(visitor.before.drain(..));
+                            stmts.extend(
+                                self.coalesce_counter_stmts(visitor.before.drain(..).collect()),
+                            );
 
                             stmts.push(stmt_body);
 
@@ -769,17 +822,29 @@ macro_rules! instrumentation_visitor {
                     // Note: unlike upstream, we do not use setAttr-based approach as it is not easy to
                     // append arbitary dynamic metadata on the parents can be accessed in any childs.
                     if ignore_current != Some(crate::hint_comments::IgnoreScope::If) {
-                        wrap_with_counter(&mut if_stmt.cons);
+                        wrap_with_counter(&mut if_stmt.cons, true);
                     }
 
-                    if ignore_current != Some(crate::hint_comments::IgnoreScope::Else) {
+                    if ignore_current == Some(crate::hint_comments::IgnoreScope::Else) {
+                        // `else if` with ignore else: the else path is not counted, but the nested if
+                        // is a statement on its own. Instrument it as usual, with its own branches.
                         if let Some(alt) = &mut if_stmt.alt {
-                            wrap_with_counter(alt);
+                            if matches!(**alt, Stmt::If(_)) {
+                                wrap_with_counter(alt, false);
+                            }
+                        }
+                    } else if let Some(alt) = &mut if_stmt.alt {
+                        wrap_with_counter(alt, true);
+                    } else if let Some(branch) = branch {
+                        if !implicit_else_branch {
+                            // Register the implicit else path without injecting the block,
+                            // its counter never increments.
+                            cov.borrow_mut().add_branch_path(branch, &range);
                         } else {
                             // alt can be none (`if some {}` without else).
                             // Inject empty blockstmt then insert branch counters
                             let mut alt = Box::new(Stmt::Block(BlockStmt::dummy()));
-                            wrap_with_counter(&mut alt);
+                            wrap_with_counter(&mut alt, true);
                             if_stmt.alt = Some(alt);
 
                             // We visit individual cons / alt depends on its state, need to run visitor for the `test` as well
@@ -798,6 +863,20 @@ macro_rules! instrumentation_visitor {
             };
         }
 
+        // JSXExpressionContainer: ignore hint right after the opening brace applies to its expression.
+        #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
+        fn visit_mut_jsx_expr_container(&mut self, container: &mut JSXExprContainer) {
+            let old = self.should_ignore;
+            if old != Some(crate::hint_comments::IgnoreScope::Next)
+                && crate::hint_comments::should_ignore_jsx_expr(&self.comments, &container.span)
+            {
+                self.should_ignore = Some(crate::hint_comments::IgnoreScope::Next);
+            }
+
+            container.visit_mut_children_with(self);
+            self.should_ignore = old;
+        }
+
         // LogicalExpression: entries(coverLogicalExpression)
         #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
         fn visit_mut_bin_expr(&mut self, bin_expr: &mut BinExpr) {
@@ -824,22 +903,29 @@ macro_rules! instrumentation_visitor {
                         BinaryOp::LogicalOr
                         | BinaryOp::LogicalAnd
                         | BinaryOp::NullishCoalescing => {
-                            self.nodes.push(crate::Node::LogicalExpr);
-
                             // Create a new branch. This id should be reused for any inner logical expr.
-                            let range = crate::lookup_range::get_range_from_span(
-                                &self.source_map,
-                                &bin_expr.span,
-                            );
-                            let branch = self.cov.borrow_mut().new_branch(
+                            let range = self.lookup_range(&bin_expr.span);
+                            let branch = self.cov.borrow_mut().try_new_branch(
                                 crate::BranchType::BinaryExpr,
                                 &range,
                                 self.instrument_options.report_logic,
                             );
 
-                            // Iterate over each expr, wrap it with branch counter.
-                            self.wrap_bin_expr_with_branch_counter(branch, &mut *bin_expr.left);
-                            self.wrap_bin_expr_with_branch_counter(branch, &mut *bin_expr.right);
+                            if let Some(branch) = branch {
+                                self.nodes.push(crate::Node::LogicalExpr);
+
+                                // Iterate over each expr, wrap it with branch counter.
+                                self.wrap_bin_expr_with_branch_counter(branch, &mut *bin_expr.left);
+                                self.wrap_bin_expr_with_branch_counter(
+                                    branch,
+                                    &mut *bin_expr.right,
+                                );
+                            } else {
+                                // Outside of the changed lines, iterate as normal for the inner counters.
+                                self.nodes.push(crate::Node::BinExpr);
+                                bin_expr.visit_mut_children_with(self);
+                                self.on_exit(old);
+                            }
                         }
                         _ => {
                             // iterate as normal for non loigical expr
@@ -852,6 +938,40 @@ macro_rules! instrumentation_visitor {
             }
         }
 
+        // Logical assignments (`&&=`, `||=`, `??=`) short-circuit same as logical exprs,
+        // the right is a branch path counted only when it's evaluated.
+        #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
+        fn visit_mut_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+            if !matches!(
+                assign_expr.op,
+                AssignOp::AndAssign | AssignOp::OrAssign | AssignOp::NullishAssign
+            ) {
+                assign_expr.visit_mut_children_with(self);
+                return;
+            }
+
+            let (old, ignore_current) = self.on_enter(assign_expr);
+            match ignore_current {
+                Some(crate::hint_comments::IgnoreScope::Next) => {}
+                _ => {
+                    let range = self.lookup_range(&assign_expr.span);
+                    let branch = self.cov.borrow_mut().try_new_branch(
+                        crate::BranchType::BinaryExpr,
+                        &range,
+                        self.instrument_options.report_logic,
+                    );
+
+                    assign_expr.left.visit_mut_with(self);
+                    if let Some(branch) = branch {
+                        self.wrap_bin_expr_with_branch_counter(branch, &mut *assign_expr.right);
+                    } else {
+                        assign_expr.right.visit_mut_with(self);
+                    }
+                }
+            }
+            self.on_exit(old);
+        }
+
         // AssignmentPattern: entries(coverAssignmentPattern),
         #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
         fn visit_mut_assign_pat(&mut self, assign_pat: &mut AssignPat) {
@@ -859,17 +979,50 @@ macro_rules! instrumentation_visitor {
             match ignore_current {
                 Some(crate::hint_comments::IgnoreScope::Next) => {}
                 _ => {
-                    let range = crate::lookup_range::get_range_from_span(
-                        &self.source_map,
-                        &assign_pat.span,
-                    );
-                    let branch = self.cov.borrow_mut().new_branch(
+                    let range = self.lookup_range(&assign_pat.span);
+                    let branch = self.cov.borrow_mut().try_new_branch(
                         crate::BranchType::DefaultArg,
                         &range,
                         false,
                     );
 
-                    self.wrap_bin_expr_with_branch_counter(branch, &mut *assign_pat.right);
+                    // Left can be a destructuring pattern have its own defaults
+                    // (`function f({ a = 1 } = {})`), visit it before the right to preserve
+                    // istanbul's branch order.
+                    assign_pat.left.visit_mut_with(self);
+                    if let Some(branch) = branch {
+                        self.wrap_bin_expr_with_branch_counter(branch, &mut *assign_pat.right);
+                    } else {
+                        assign_pat.right.visit_mut_with(self);
+                    }
+                }
+            }
+            self.on_exit(old);
+        }
+
+        // AssignmentPattern: entries(coverAssignmentPattern),
+        // Unlike babel, SWC represents shorthand default in object pattern (`{ a = 1 }`)
+        // as a separate node instead of AssignmentPattern.
+        #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
+        fn visit_mut_assign_pat_prop(&mut self, assign_pat_prop: &mut AssignPatProp) {
+            let (old, ignore_current) = self.on_enter(assign_pat_prop);
+            match ignore_current {
+                Some(crate::hint_comments::IgnoreScope::Next) => {}
+                _ => {
+                    if let Some(value) = &mut assign_pat_prop.value {
+                        let range = self.lookup_range(&assign_pat_prop.span);
+                        let branch = self.cov.borrow_mut().try_new_branch(
+                            crate::BranchType::DefaultArg,
+                            &range,
+                            false,
+                        );
+
+                        if let Some(branch) = branch {
+                            self.wrap_bin_expr_with_branch_counter(branch, &mut **value);
+                        } else {
+                            value.visit_mut_with(self);
+                        }
+                    }
                 }
             }
             self.on_exit(old);
@@ -903,41 +1056,23 @@ macro_rules! instrumentation_visitor {
             self.on_exit(old);
         }
 
-        // WithStatement: entries(blockProp('body'), coverStatement),
+        // DebuggerStatement: entries(coverStatement),
         #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
-        fn visit_mut_with_stmt(&mut self, with_stmt: &mut WithStmt) {
-            let (old, ignore_current) = self.on_enter(with_stmt);
+        fn visit_mut_debugger_stmt(&mut self, debugger_stmt: &mut DebuggerStmt) {
+            let (old, ignore_current) = self.on_enter(debugger_stmt);
             match ignore_current {
                 Some(crate::hint_comments::IgnoreScope::Next) => {}
                 _ => {
-                    self.mark_prepend_stmt_counter(&with_stmt.span);
-
-                    //TODO: duplicated codes for wrapping block
-                    if let Stmt::Block(body_block) = &mut *with_stmt.body {
-                        self.insert_stmts_counter(&mut body_block.stmts);
-                    } else {
-                        let mut visitor = crate::visitors::stmt_like_visitor::StmtVisitor::new(
-                            self.source_map.clone(),
-                            self.comments.clone(),
-                            self.cov.clone(),
-                            self.instrument_options.clone(),
-                            self.nodes.clone(),
-                            ignore_current,
-                        );
-                        with_stmt.body.visit_mut_with(&mut visitor);
-                        let mut new_stmts = vec![];
-                        new_stmts.extend(visitor.before.drain(..));
-                        new_stmts.push(*with_stmt.body.take());
-
-                        with_stmt.body = Box::new(Stmt::Block(BlockStmt {
-                            span: swc_core::common::DUMMY_SP,
-                            stmts: new_stmts,
-                            ..Default::default()
-                        }));
-                    }
+                    self.mark_prepend_stmt_counter(&debugger_stmt.span);
                 }
             }
             self.on_exit(old);
         }
+
+        // WithStatement: entries(blockProp('body'), coverStatement),
+        #[tracing::instrument(skip_all, fields(node = %self.print_node()))]
+        fn visit_mut_with_stmt(&mut self, with_stmt: &mut WithStmt) {
+            crate::visit_mut_for_like!(self, with_stmt);
+        }
     };
 }
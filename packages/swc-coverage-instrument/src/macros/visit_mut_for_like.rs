@@ -1,5 +1,6 @@
 /// A macro creates body for the for-variant visitors (for, for-of, for-in) which
-/// shares same logic. This also works for other loops like while, do-while.
+/// shares same logic. This also works for other loops like while, do-while,
+/// and `with` statement which have the same shape of `body`.
 #[macro_export]
 macro_rules! visit_mut_for_like {
     ($self: ident, $for_like_stmt: ident) => {
@@ -12,20 +13,28 @@ macro_rules! visit_mut_for_like {
                 $self.mark_prepend_stmt_counter(&$for_like_stmt.span);
 
                 let body = *$for_like_stmt.body.take();
-                // if for stmt body is not block, wrap it before insert statement counter
-                let body = if let Stmt::Block(body) = body {
-                    body
-                } else {
-                    let stmts = vec![body];
-                    BlockStmt {
+                // if for stmt body is not block, wrap it before insert statement counter.
+                // Empty body (i.e `for (;;);`) becomes an empty block.
+                let body = match body {
+                    Stmt::Block(body) => body,
+                    Stmt::Empty(_) => BlockStmt {
                         span: swc_core::common::DUMMY_SP,
-                        stmts,
+                        stmts: vec![],
                         ..Default::default()
-                    }
+                    },
+                    body => BlockStmt {
+                        span: swc_core::common::DUMMY_SP,
+                        stmts: vec![body],
+                        ..Default::default()
+                    },
                 };
 
+                // Only the body is replaced; header flags like `is_await` of `for await...of` are kept as-is.
                 $for_like_stmt.body = Box::new(Stmt::Block(body));
-                // Iterate children for inner stmt's counter insertion
+                // Iterate children for inner stmt's counter insertion.
+                // This also visits loop headers (init / test / update for `for`, left / right for
+                // for-in / for-of, test for while-likes) so logical / conditional expressions in
+                // those clauses register their own branches, `object` for with.
                 $for_like_stmt.visit_mut_children_with($self);
             }
         }
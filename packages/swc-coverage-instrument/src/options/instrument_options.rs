@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use istanbul_oxide::SourceMap;
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +19,84 @@ impl Default for InstrumentLogOptions {
     }
 }
 
+/// Property names of the counters in the coverage object.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InstrumentCounterIdentOptions {
+    pub statement: String,
+    pub function: String,
+    pub branch: String,
+}
+
+impl Default for InstrumentCounterIdentOptions {
+    fn default() -> Self {
+        InstrumentCounterIdentOptions {
+            statement: "s".to_string(),
+            function: "f".to_string(),
+            branch: "b".to_string(),
+        }
+    }
+}
+
+/// Determines when the coverage object is registered into the global coverage variable.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InitializeCoverage {
+    /// Explicitly call the coverage fn at the top of the file.
+    Always,
+    /// Omit the explicit call, coverage is initialized when any of the counters is hit first.
+    Lazy,
+}
+
+impl Default for InitializeCoverage {
+    fn default() -> Self {
+        InitializeCoverage::Always
+    }
+}
+
+/// Determines the form of the counter increments.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IncrementStyle {
+    /// `cov().s[0]++`
+    Increment,
+    /// `cov().s[0] = cov().s[0] + 1`, for the runtimes proxying the coverage object
+    /// where the update expr behaves differently than the assignment under Proxy traps.
+    Assign,
+}
+
+impl Default for IncrementStyle {
+    fn default() -> Self {
+        IncrementStyle::Increment
+    }
+}
+
+/// Encoding of the coverage data embedded in the coverage data comment.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetadataEncoding {
+    /// Plain JSON.
+    Json,
+    /// MessagePack encoded with named fields, then base64 encoded. Smaller for the large files.
+    MsgpackBase64,
+}
+
+impl Default for MetadataEncoding {
+    fn default() -> Self {
+        MetadataEncoding::Json
+    }
+}
+
+/// Predefined compositions of the options for the specific use cases.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InstrumentPreset {
+    /// Minimal overhead for the mutation testing tools, which only need statement and branch coverage.
+    /// Disables function counters (`instrument_functions: false`), uses compact metadata
+    /// (`compact: true`) and omits the explicit init call (`initialize_coverage: Lazy`).
+    Mutation,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", default)]
 pub struct InstrumentOptions {
@@ -27,10 +107,100 @@ pub struct InstrumentOptions {
     pub input_source_map: Option<SourceMap>,
     pub instrument_log: InstrumentLogOptions,
     pub debug_initial_coverage_comment: bool,
+    // Prefix of the comment carrying initial coverage data when `debug_initial_coverage_comment` is enabled.
+    pub coverage_comment_prefix: String,
     // Allow to specify which files should be excluded from instrumentation.
     // This option accepts an array of wax(https://crates.io/crates/wax)-compatible glob patterns
     // and will match against the filename provided by swc's core.
     pub unstable_exclude: Option<Vec<String>>,
+    // Skip instrumentation for the files look like generated / minified code.
+    // A file is considered as minified if its average line length exceeds `minified_line_length_threshold`.
+    pub skip_if_minified: bool,
+    pub minified_line_length_threshold: u32,
+    // Override property names of the statement / function / branch counters,
+    // i.e to coexist with another coverage runtime.
+    pub counter_idents: InstrumentCounterIdentOptions,
+    // Back function counters with a `Uint32Array` (when the runtime supports it), increased by `+=`
+    // instead of property increment. Reduces overhead of counting in hot functions,
+    // but the initial coverage object is no longer a plain object literal.
+    pub typed_array_fn_counters: bool,
+    pub initialize_coverage: InitializeCoverage,
+    // Attach a comment with the total number of instrumented statements / branches / functions,
+    // i.e to detect files unexpectedly have no instrumented statements.
+    pub emit_summary: bool,
+    // Register a `process.once("exit")` hook assigns the coverage data into the global coverage variable
+    // again, for the environments may reset it before the reporter reads.
+    pub flush_on_exit: bool,
+    // Only instrument files having the given marker comment, i.e `istanbul include file`.
+    // Files without the marker are left as-is.
+    pub include_file_comment: Option<String>,
+    // Leave the file uninstrumented if the total number of statement / function / branch counters
+    // exceeds the limit, i.e for the large generated files.
+    pub max_counters: Option<u32>,
+    // Inject an empty `else {}` block with a branch counter for the `if` without else.
+    // If disabled, the else path is still registered in the branch map but never counted.
+    pub implicit_else_branch: bool,
+    // Leave the file as-is if its source text looks already instrumented,
+    // i.e instrumented by a previous tool.
+    pub detect_pre_instrumented: bool,
+    // Register coverage data under `${coverage_variable}_${namespace}` instead of `coverage_variable`,
+    // i.e by a build id to avoid concurrent runs sharing globals clobber each other.
+    pub coverage_variable_namespace: Option<String>,
+    // Increase counters of the consecutive statements unlikely to interrupt execution (no calls, member
+    // accesses or branches) at once at the entry, instead of per each statement.
+    // Reduces instrumentation overhead in hot loops. This is a heuristic: a batched statement can
+    // still throw (i.e reading an undeclared identifier, the temporal dead zone or mixing BigInt
+    // with Number), then the counters of the statements after it are over-reported.
+    pub optimize_straight_line_blocks: bool,
+    // Merge adjacent counters prepended to the same statement into a single sequence expression
+    // statement, i.e `var a = function () {}, b = function () {};`. Reduces the output size only,
+    // counts are same.
+    pub coalesce_counters: bool,
+    // Import the coverage data embedded by the previous instrumentation (`debug_initial_coverage_comment`)
+    // and append new counters to it, instead of starting fresh, i.e for multi-stage builds.
+    // The previous coverage fn is replaced, so its counters are counted into the merged coverage data.
+    pub merge_existing: bool,
+    // Create fn counters for arrow functions. If disabled, arrow functions (i.e small callbacks like
+    // `arr.map(x => x + 1)`) are not tracked as functions, while statements in their bodies are.
+    pub instrument_arrow_functions: bool,
+    // Create fn counters for functions. If disabled, the fn map is empty while statements and branches
+    // in their bodies are still counted.
+    pub instrument_functions: bool,
+    // Only create counters for the statements / functions / branches intersecting the changed lines,
+    // i.e for the PR coverage. Keyed by the filename, each value is a list of inclusive `[start, end]`
+    // 1-based line ranges. Files not listed have no changed lines, thus no counters.
+    pub changed_lines: Option<HashMap<String, Vec<(u32, u32)>>>,
+    // Report the nodes not able to be instrumented (i.e class methods with computed keys)
+    // in a `__coverage_skipped__::` comment, for the guarantee the whole file is instrumented.
+    pub strict_coverage: bool,
+    // Module specifier of the shared coverage runtime, i.e `@scope/coverage-runtime`. If set, ES modules
+    // import its `cov` export and register coverage data via it instead of inlining the global lookup
+    // in every file. Scripts are not able to import, still inline it.
+    pub coverage_runtime: Option<String>,
+    // Emit the counter wrapped exprs as a bare sequence expr (`cov().s[0]++, expr`) instead of wrapping
+    // it with a `ParenExpr`. Parens are added only where needed by swc's `fixer` pass, which runs after
+    // custom passes and plugins. Custom pipelines should run `fixer` before printing.
+    pub omit_counter_parens: bool,
+    // Expression evaluated to the global scope the coverage variable is registered into, i.e `this`
+    // or `globalThis`. `auto` (default) tries `globalThis`, `self` and `window` in order, then falls
+    // back to `this` of a `Function` constructor. Node's `global` is not tried, as it is shadowed by the
+    // hoisted `var global` the lookup is assigned to.
+    pub coverage_global_scope: String,
+    // Form of the statement / branch / function counter increments. Function counters backed by
+    // typed array (`typed_array_fn_counters`) always use `+=`.
+    pub increment_style: IncrementStyle,
+    // Omit statement counters for the statements without observable effect of their own, i.e a lone
+    // identifier reference or `break` following another statement. Changes the statement totals.
+    pub skip_trivial_statements: bool,
+    // Encoding of the coverage data embedded by `debug_initial_coverage_comment`.
+    // Hosts decode the comment payload via `decode_coverage_data`.
+    pub metadata_encoding: MetadataEncoding,
+    // Emit `l` line hits in the coverage data. Same as istanbul's line coverage the reporters derive,
+    // hits of a line is the max hits of the statements start at the line, computed from the statement
+    // hits whenever it is read. No additional counters are inserted.
+    pub track_lines: bool,
+    // Overrides the options set by the preset, applied when the visitor is created.
+    pub preset: Option<InstrumentPreset>,
 }
 
 impl Default for InstrumentOptions {
@@ -43,7 +213,65 @@ impl Default for InstrumentOptions {
             input_source_map: Default::default(),
             instrument_log: Default::default(),
             debug_initial_coverage_comment: false,
+            coverage_comment_prefix: crate::COVERAGE_DATA_JSON_COMMENT_PREFIX.to_string(),
             unstable_exclude: Default::default(),
+            skip_if_minified: false,
+            minified_line_length_threshold: 1000,
+            counter_idents: Default::default(),
+            typed_array_fn_counters: false,
+            initialize_coverage: Default::default(),
+            emit_summary: false,
+            flush_on_exit: false,
+            include_file_comment: None,
+            max_counters: None,
+            implicit_else_branch: true,
+            detect_pre_instrumented: false,
+            coverage_variable_namespace: None,
+            optimize_straight_line_blocks: false,
+            coalesce_counters: false,
+            merge_existing: false,
+            instrument_arrow_functions: true,
+            instrument_functions: true,
+            changed_lines: None,
+            strict_coverage: false,
+            coverage_runtime: None,
+            omit_counter_parens: false,
+            coverage_global_scope: crate::COVERAGE_GLOBAL_SCOPE_AUTO.to_string(),
+            increment_style: Default::default(),
+            skip_trivial_statements: false,
+            metadata_encoding: Default::default(),
+            track_lines: false,
+            preset: None,
         }
     }
 }
+
+impl InstrumentOptions {
+    /// Returns the options with the values of `preset` applied, if any.
+    pub fn with_preset_applied(self) -> Self {
+        match self.preset {
+            Some(InstrumentPreset::Mutation) => InstrumentOptions {
+                instrument_functions: false,
+                compact: true,
+                initialize_coverage: InitializeCoverage::Lazy,
+                ..self
+            },
+            None => self,
+        }
+    }
+
+    /// Returns the global coverage variable namespaced by `coverage_variable_namespace`,
+    /// i.e `__coverage___${build_id}`.
+    pub fn namespaced_coverage_variable(&self) -> String {
+        match &self.coverage_variable_namespace {
+            Some(namespace) => format!("{}_{}", self.coverage_variable, namespace),
+            None => self.coverage_variable.clone(),
+        }
+    }
+
+    /// Returns the prefix of the coverage data comment, if `debug_initial_coverage_comment` is set.
+    pub fn debug_comment_prefix(&self) -> Option<&str> {
+        self.debug_initial_coverage_comment
+            .then_some(self.coverage_comment_prefix.as_str())
+    }
+}
@@ -33,6 +33,12 @@ impl Default for SourceCoverageMeta {
 pub struct SourceCoverage {
     inner: FileCoverage,
     meta: SourceCoverageMeta,
+    // Inclusive line ranges to create counters for, if only the changed lines are instrumented.
+    changed_lines: Option<Vec<(u32, u32)>>,
+    // Whether to create function counters at all.
+    instrument_functions: bool,
+    // Nodes not able to be instrumented with their locations, reported under `strict_coverage`.
+    skipped_nodes: Vec<(String, Range)>,
 }
 
 impl SourceCoverage {
@@ -40,6 +46,9 @@ impl SourceCoverage {
         SourceCoverage {
             inner: FileCoverage::from_file_path(file_path, report_logic),
             meta: Default::default(),
+            changed_lines: None,
+            instrument_functions: true,
+            skipped_nodes: vec![],
         }
     }
 
@@ -57,6 +66,11 @@ impl SourceCoverage {
         s
     }
 
+    /// Creates a statement only if its location intersects the changed lines.
+    pub fn try_new_statement(&mut self, loc: &Range) -> Option<u32> {
+        self.is_changed(loc).then(|| self.new_statement(loc))
+    }
+
     pub fn new_function(&mut self, name: &Option<String>, decl: &Range, loc: &Range) -> u32 {
         let f = self.meta.last.f;
         let name = if let Some(name) = name {
@@ -81,6 +95,18 @@ impl SourceCoverage {
         f
     }
 
+    /// Creates a function only if function counters are enabled and its location intersects
+    /// the changed lines.
+    pub fn try_new_function(
+        &mut self,
+        name: &Option<String>,
+        decl: &Range,
+        loc: &Range,
+    ) -> Option<u32> {
+        (self.instrument_functions && self.is_changed(loc))
+            .then(|| self.new_function(name, decl, loc))
+    }
+
     pub fn new_branch(
         &mut self,
         branch_type: BranchType,
@@ -105,6 +131,29 @@ impl SourceCoverage {
         b
     }
 
+    /// Creates a branch only if its location intersects the changed lines.
+    /// Paths of the created branch are always added regardless of the changed lines.
+    pub fn try_new_branch(
+        &mut self,
+        branch_type: BranchType,
+        loc: &Range,
+        is_report_logic: bool,
+    ) -> Option<u32> {
+        self.is_changed(loc)
+            .then(|| self.new_branch(branch_type, loc, is_report_logic))
+    }
+
+    /// Determine if given location intersects the changed lines. Every location is considered
+    /// as changed unless the changed lines are set.
+    fn is_changed(&self, loc: &Range) -> bool {
+        match &self.changed_lines {
+            Some(changed_lines) => changed_lines
+                .iter()
+                .any(|(start, end)| loc.start.line <= *end && loc.end.line >= *start),
+            None => true,
+        }
+    }
+
     fn maybe_new_branch_true(
         &mut self,
         branch_type: &BranchType,
@@ -128,6 +177,10 @@ impl SourceCoverage {
         }
     }
 
+    /// Append a path location to the given branch, returns the index of the path.
+    /// Locations are recorded in the order of the call: visitors should add paths in source order
+    /// (left then right for the logical expr, consequent then alternate for if / conditional expr)
+    /// since reporters rely on the `locations` ordering to render which side was taken.
     pub fn add_branch_path(&mut self, name: u32, location: &Range) -> u32 {
         let b_meta = self
             .inner
@@ -163,10 +216,69 @@ impl SourceCoverage {
         }
     }
 
+    /// Import counters of the existing coverage data, i.e embedded by the previous instrumentation.
+    /// Imported counters keep their indices and hit counts, counters created after this
+    /// are appended with the subsequent indices.
+    pub fn import(&mut self, existing: &FileCoverage) {
+        for (key, loc) in &existing.statement_map {
+            self.inner.statement_map.insert(*key, loc.clone());
+            self.inner
+                .s
+                .insert(*key, existing.s.get(key).copied().unwrap_or(0));
+        }
+
+        for (key, function) in &existing.fn_map {
+            self.inner.fn_map.insert(*key, function.clone());
+            self.inner
+                .f
+                .insert(*key, existing.f.get(key).copied().unwrap_or(0));
+        }
+
+        for (key, branch) in &existing.branch_map {
+            let counts = existing
+                .b
+                .get(key)
+                .cloned()
+                .unwrap_or_else(|| vec![0; branch.locations.len()]);
+            self.inner.branch_map.insert(*key, branch.clone());
+            self.inner.b.insert(*key, counts);
+
+            if let (Some(b_t), Some(existing_b_t)) = (&mut self.inner.b_t, &existing.b_t) {
+                if let Some(counts_true) = existing_b_t.get(key) {
+                    b_t.insert(*key, counts_true.clone());
+                }
+            }
+        }
+
+        let next = |last: u32, key: Option<&u32>| last.max(key.map_or(0, |key| key + 1));
+        self.meta.last = SourceCoverageMetaHitCount {
+            s: next(self.meta.last.s, existing.statement_map.keys().max()),
+            f: next(self.meta.last.f, existing.fn_map.keys().max()),
+            b: next(self.meta.last.b, existing.branch_map.keys().max()),
+        };
+    }
+
     pub fn set_input_source_map(&mut self, source_map: &Option<SourceMap>) {
         self.inner.input_source_map = source_map.clone();
     }
 
+    pub fn set_changed_lines(&mut self, changed_lines: Option<Vec<(u32, u32)>>) {
+        self.changed_lines = changed_lines;
+    }
+
+    pub fn set_instrument_functions(&mut self, instrument_functions: bool) {
+        self.instrument_functions = instrument_functions;
+    }
+
+    /// Record a node the instrumentation does not support, thus has no counters.
+    pub fn add_skipped_node(&mut self, node: &str, loc: &Range) {
+        self.skipped_nodes.push((node.to_string(), loc.clone()));
+    }
+
+    pub fn skipped_nodes(&self) -> &[(String, Range)] {
+        &self.skipped_nodes
+    }
+
     pub fn freeze(&mut self) {
         // prune empty branches
         let map = &mut self.inner.branch_map;
@@ -360,6 +472,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_import_existing_coverage() {
+        let mut existing = SourceCoverage::new("anon".to_string(), false);
+        let dummy_range = Range::new(2, 3, 5, 2);
+        existing.new_statement(&dummy_range);
+        existing.new_function(&None, &dummy_range, &dummy_range);
+        let branch = existing.new_branch(BranchType::CondExpr, &dummy_range, false);
+        existing.add_branch_path(branch, &dummy_range);
+        existing.inner.s.insert(0, 3);
+
+        let mut coverage = SourceCoverage::new("anon".to_string(), false);
+        coverage.import(existing.as_ref());
+
+        let new_range = Range::new(6, 0, 6, 10);
+        assert_eq!(coverage.new_statement(&new_range), 1);
+        assert_eq!(coverage.new_function(&None, &new_range, &new_range), 1);
+        assert_eq!(coverage.new_branch(BranchType::If, &new_range, false), 1);
+
+        let coverage_ref = coverage.as_ref();
+        assert_eq!(coverage_ref.s.get(&0), Some(&3));
+        assert_eq!(coverage_ref.statement_map.get(&0), Some(&dummy_range));
+        assert_eq!(coverage_ref.statement_map.get(&1), Some(&new_range));
+        assert_eq!(coverage_ref.b.get(&0), Some(&vec![0]));
+    }
+
     #[test]
     fn should_freeze() {
         let mut coverage = SourceCoverage::new("anon".to_string(), false);
@@ -0,0 +1,27 @@
+use std::collections::HashSet;
+
+use swc_core::{
+    common::{BytePos, Span},
+    ecma::{
+        ast::Program,
+        visit::{Visit, VisitWith},
+    },
+};
+
+/// Collects the positions comments can be attached to, which are the boundaries of the nodes.
+struct CommentPosCollector(HashSet<BytePos>);
+
+impl Visit for CommentPosCollector {
+    fn visit_span(&mut self, span: &Span) {
+        self.0.insert(span.lo);
+        self.0.insert(span.hi);
+    }
+}
+
+/// Returns the positions in the program comments can be attached to.
+/// `Comments` can't enumerate the comments it stores, those are looked up by these positions.
+pub fn collect_comment_positions(program: &Program) -> HashSet<BytePos> {
+    let mut positions = CommentPosCollector(HashSet::new());
+    program.visit_with(&mut positions);
+    positions.0
+}
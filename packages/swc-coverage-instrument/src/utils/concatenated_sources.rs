@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+use regex::Regex as Regexp;
+use swc_core::{
+    common::{comments::Comments, SourceMapper, Spanned},
+    ecma::ast::Program,
+};
+
+use crate::comment_positions::collect_comment_positions;
+
+/// Matches the text of the comment, without its `//` or `/*` delimiter.
+static SOURCE_MAPPING_URL_REGEX: Lazy<Regexp> =
+    Lazy::new(|| Regexp::new(r"^[#@]\s*sourceMappingURL=").unwrap());
+
+/// Count the original files concatenated into the given program, delimited by
+/// `sourceMappingURL` comments. The comment of the last file trails after the program span,
+/// so each comment found inside of the span is followed by another file.
+///
+/// Only the actual comments are counted, the same text inside of string or template literals
+/// is not a delimiter.
+///
+/// Instrumentation creates a single coverage object keyed by the filename for the whole
+/// program, coverage of the concatenated files can't be reported separately.
+pub fn count_concatenated_sources<C: Comments, S: SourceMapper>(
+    source_map: &Arc<S>,
+    comments: C,
+    program: &Program,
+) -> usize {
+    let span = program.span();
+    if span.hi.is_dummy() || span.lo.is_dummy() || span.hi <= span.lo {
+        return 1;
+    }
+
+    // Walking the comments is only needed if the source text mentions it at all.
+    match source_map.span_to_snippet(span) {
+        Ok(source) if source.contains("sourceMappingURL=") => {}
+        _ => return 1,
+    }
+
+    let delimiters = collect_comment_positions(program)
+        .into_iter()
+        .flat_map(|pos| {
+            comments
+                .get_leading(pos)
+                .into_iter()
+                .chain(comments.get_trailing(pos))
+                .flatten()
+        })
+        .filter(|comment| {
+            comment.span.lo >= span.lo
+                && comment.span.hi <= span.hi
+                && SOURCE_MAPPING_URL_REGEX.is_match(&comment.text)
+        })
+        .count();
+
+    delimiters + 1
+}
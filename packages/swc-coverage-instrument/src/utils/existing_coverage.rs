@@ -0,0 +1,53 @@
+use istanbul_oxide::FileCoverage;
+use swc_core::common::{
+    comments::{Comment, Comments},
+    Span,
+};
+
+/// Take out the coverage data comments (`{prefix}{payload}`) from the given comments,
+/// returns the last valid coverage data.
+fn take_coverage_comments(comments: &mut Vec<Comment>, prefix: &str) -> Option<FileCoverage> {
+    let mut existing = None;
+
+    comments.retain(|comment| match comment.text.strip_prefix(prefix) {
+        Some(payload) => {
+            // `*\/` escaped by the instrumentation is a valid JSON escape, no need to unescape.
+            if let Ok(coverage) = crate::decode_coverage_data(payload) {
+                existing = Some(coverage);
+            }
+            false
+        }
+        None => true,
+    });
+
+    existing
+}
+
+/// Look up the coverage data embedded by the previous instrumentation
+/// (`debug_initial_coverage_comment`) attached to the program, and removes the comment
+/// so only the coverage data of the new instrumentation is embedded.
+pub fn take_existing_coverage<C: Clone + Comments>(
+    comments: &C,
+    span: &Span,
+    prefix: &str,
+) -> Option<FileCoverage> {
+    let mut existing = None;
+
+    for pos in [span.lo, span.hi] {
+        if let Some(mut leading) = comments.take_leading(pos) {
+            existing = take_coverage_comments(&mut leading, prefix).or(existing);
+            if !leading.is_empty() {
+                comments.add_leading_comments(pos, leading);
+            }
+        }
+
+        if let Some(mut trailing) = comments.take_trailing(pos) {
+            existing = take_coverage_comments(&mut trailing, prefix).or(existing);
+            if !trailing.is_empty() {
+                comments.add_trailing_comments(pos, trailing);
+            }
+        }
+    }
+
+    existing
+}
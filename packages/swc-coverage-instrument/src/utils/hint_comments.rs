@@ -3,7 +3,7 @@ use regex::Regex as Regexp;
 use swc_core::{
     common::{
         comments::{Comment, Comments},
-        Span,
+        BytePos, Span,
     },
     ecma::ast::*,
 };
@@ -19,7 +19,12 @@ static COMMENT_FILE_REGEX: Lazy<Regexp> =
 pub static COMMENT_RE: Lazy<Regexp> =
     Lazy::new(|| Regexp::new(r"^\s*istanbul\s+ignore\s+(if|else|next)(\W|$)").unwrap());
 
-pub fn should_ignore_file<C: Clone + Comments>(comments: &C, program: &Program) -> bool {
+/// Determine if any of the comments attached to the program matches the given pattern.
+fn has_file_comment<C: Clone + Comments>(
+    comments: &C,
+    program: &Program,
+    pattern: &Regexp,
+) -> bool {
     let pos = match &program {
         Program::Module(module) => module.span,
         Program::Script(script) => script.span,
@@ -29,7 +34,7 @@ pub fn should_ignore_file<C: Clone + Comments>(comments: &C, program: &Program)
         if let Some(comments) = comments {
             comments
                 .iter()
-                .any(|comment| COMMENT_FILE_REGEX.is_match(&comment.text))
+                .any(|comment| pattern.is_match(&comment.text))
         } else {
             false
         }
@@ -45,6 +50,29 @@ pub fn should_ignore_file<C: Clone + Comments>(comments: &C, program: &Program)
     .any(|c| validate_comments(c))
 }
 
+pub fn should_ignore_file<C: Clone + Comments>(comments: &C, program: &Program) -> bool {
+    has_file_comment(comments, program, &COMMENT_FILE_REGEX)
+}
+
+/// Determine if the program has the opt-in marker comment (i.e `/* istanbul include file */`).
+/// Whitespaces between the words of the marker match any whitespaces, same as ignore hints.
+pub fn should_include_file<C: Clone + Comments>(
+    comments: &C,
+    program: &Program,
+    marker: &str,
+) -> bool {
+    let marker = marker
+        .split_whitespace()
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(r"\s+");
+
+    match Regexp::new(&format!(r"^\s*{}(\W|$)", marker)) {
+        Ok(pattern) => has_file_comment(comments, program, &pattern),
+        Err(_) => false,
+    }
+}
+
 pub fn lookup_hint_comments<C: Clone + Comments>(
     comments: &C,
     span: Option<&Span>,
@@ -105,3 +133,34 @@ pub fn should_ignore<C: Clone + Comments>(
         None
     }
 }
+
+/// Determine if the branch path (conditional expr's consequent / alternate, logical expr's operand)
+/// of the given span should be ignored.
+/// In addition to the leading hint, branch paths accept a trailing `istanbul ignore next` comment
+/// placed right after the path expression, before the next token of the parent expression:
+/// `a ? b /* istanbul ignore next */ : c` or `a /* istanbul ignore next */ || b`.
+pub fn should_ignore_branch_path<C: Clone + Comments>(comments: &C, span: &Span) -> bool {
+    if should_ignore(comments, Some(span)) == Some(IgnoreScope::Next) {
+        return true;
+    }
+
+    has_trailing_ignore_next(comments, span.hi)
+}
+
+/// Determine if the expression of the JSX expression container of the given span should be ignored.
+/// A hint right after the opening brace (`{/* istanbul ignore next */ cond && <A />}`) is attached as
+/// the trailing comment of the brace, not as the leading comment of the expression.
+pub fn should_ignore_jsx_expr<C: Clone + Comments>(comments: &C, container_span: &Span) -> bool {
+    has_trailing_ignore_next(comments, container_span.lo + BytePos(1))
+}
+
+fn has_trailing_ignore_next<C: Clone + Comments>(comments: &C, pos: BytePos) -> bool {
+    comments.get_trailing(pos).map_or(false, |trailing| {
+        trailing.iter().any(|c| {
+            COMMENT_RE
+                .captures(&c.text)
+                .and_then(|captures| captures.get(1))
+                .map_or(false, |scope| scope.as_str() == "next")
+        })
+    })
+}
@@ -1,10 +1,58 @@
-use std::sync::Arc;
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
 
 use istanbul_oxide::Range;
 
-use swc_core::common::{SourceMapper, Span};
+use swc_core::{
+    common::{BytePos, SourceMapper, Span},
+    ecma::ast::Decorator,
+};
 
-pub fn get_range_from_span<S: SourceMapper>(source_map: &Arc<S>, span: &Span) -> Range {
+/// Converts span into istanbul's range. Lines are 1-based, columns are 0-based.
+/// End position points to the position after the last character, as span's `hi` is exclusive
+/// same as babel's `loc.end` istanbul uses.
+/// Positions are memoized via the given cache.
+pub fn get_range_from_span<S: SourceMapper>(
+    source_map: &Arc<S>,
+    cache: &LookupCache,
+    span: &Span,
+) -> Range {
+    create_range(span, |pos| cache.lookup(source_map, pos))
+}
+
+/// Same as `get_range_from_span`, looking up the source map for every position.
+/// Baseline of the position lookups for the benches.
+pub fn get_range_from_span_uncached<S: SourceMapper>(source_map: &Arc<S>, span: &Span) -> Range {
+    create_range(span, |pos| lookup_line_col(source_map, pos))
+}
+
+/// Cache of (line, column) per position. Nodes commonly share their boundaries
+/// (i.e a statement and its expression, or the last statement and the enclosing block),
+/// each of them looks up the same position again otherwise.
+#[derive(Debug, Default)]
+pub struct LookupCache {
+    positions: RefCell<HashMap<BytePos, (u32, u32)>>,
+}
+
+impl LookupCache {
+    fn lookup<S: SourceMapper>(&self, source_map: &Arc<S>, pos: BytePos) -> (u32, u32) {
+        *self
+            .positions
+            .borrow_mut()
+            .entry(pos)
+            .or_insert_with(|| lookup_line_col(source_map, pos))
+    }
+}
+
+fn lookup_line_col<S: SourceMapper>(source_map: &Arc<S>, pos: BytePos) -> (u32, u32) {
+    let loc = source_map.lookup_char_pos(pos);
+    // TODO: swc_plugin::source_map::Pos to use to_u32() instead
+    (loc.line as u32, loc.col.0 as u32)
+}
+
+fn create_range<F>(span: &Span, lookup: F) -> Range
+where
+    F: Fn(BytePos) -> (u32, u32),
+{
     // https://github.com/swc-project/swc/issues/5535
     // There are some node types SWC passes transformed instead of original,
     // which are not able to locate original locations.
@@ -14,14 +62,59 @@ pub fn get_range_from_span<S: SourceMapper>(source_map: &Arc<S>, span: &Span) ->
         return Default::default();
     }
 
-    let span_hi_loc = source_map.lookup_char_pos(span.hi);
-    let span_lo_loc = source_map.lookup_char_pos(span.lo);
+    let (hi_line, hi_col) = lookup(span.hi);
+    let (lo_line, lo_col) = lookup(span.lo);
 
-    Range::new(
-        span_lo_loc.line as u32,
-        // TODO: swc_plugin::source_map::Pos to use to_u32() instead
-        span_lo_loc.col.0 as u32,
-        span_hi_loc.line as u32,
-        span_hi_loc.col.0 as u32,
-    )
+    Range::new(lo_line, lo_col, hi_line, hi_col)
+}
+
+/// Excludes leading decorators from the span, i.e `@dec class {}` starts at the `class` keyword
+/// same as istanbul's `loc`. Returns the span as-is if it does not cover the decorators.
+pub fn skip_decorators<S: SourceMapper>(
+    source_map: &Arc<S>,
+    span: &Span,
+    decorators: &[Decorator],
+) -> Span {
+    let decorators_hi = match decorators.iter().map(|decorator| decorator.span.hi).max() {
+        Some(hi) if hi > span.lo && hi < span.hi => hi,
+        _ => return *span,
+    };
+
+    // Skip whitespaces between the last decorator and the keyword.
+    match source_map.span_to_snippet(Span::new(decorators_hi, span.hi)) {
+        Ok(snippet) => {
+            let offset = snippet.len() - snippet.trim_start().len();
+            Span::new(decorators_hi + BytePos(offset as u32), span.hi)
+        }
+        Err(_) => *span,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::common::{FileName, SourceMap};
+
+    use super::*;
+
+    #[test]
+    fn should_return_same_range_with_cache() {
+        let source_map: Arc<SourceMap> = Default::default();
+        let code = "var a = 1;\nfunction foo() {\n  return a ? 1 : 2;\n}\n\nfoo();\n";
+        let fm = source_map.new_source_file(FileName::Anon.into(), code.to_string());
+
+        let cache = LookupCache::default();
+        let len = code.len() as u32;
+        // Look up every span twice, the second pass is served from the cache.
+        for _ in 0..2 {
+            for lo in 0..len {
+                for hi in lo..=len {
+                    let span = Span::new(fm.start_pos + BytePos(lo), fm.start_pos + BytePos(hi));
+                    assert_eq!(
+                        get_range_from_span(&source_map, &cache, &span),
+                        get_range_from_span_uncached(&source_map, &span)
+                    );
+                }
+            }
+        }
+    }
 }
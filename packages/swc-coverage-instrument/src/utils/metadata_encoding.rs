@@ -0,0 +1,65 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use istanbul_oxide::FileCoverage;
+
+use crate::MetadataEncoding;
+
+/// Serializes coverage data to be embedded in the coverage data comment.
+/// `*/` in the JSON payload (i.e file path, source contents of the input source map) closes
+/// the block comment early, it is escaped as `*\/` which is parsed back to the same value.
+/// Base64 payload of `msgpack-base64` never contains it.
+pub fn encode_coverage_data(coverage_data: &FileCoverage, encoding: MetadataEncoding) -> String {
+    match encoding {
+        MetadataEncoding::Json => serde_json::to_string(coverage_data)
+            .expect("Should able to serialize coverage data")
+            .replace("*/", "*\\/"),
+        MetadataEncoding::MsgpackBase64 => STANDARD.encode(
+            rmp_serde::to_vec_named(coverage_data).expect("Should able to serialize coverage data"),
+        ),
+    }
+}
+
+/// Deserializes the payload of the coverage data comment. Encoding is detected from the payload:
+/// JSON payload always starts with `{`, which is not a valid base64 character.
+pub fn decode_coverage_data(payload: &str) -> Result<FileCoverage, String> {
+    if payload.starts_with('{') {
+        return serde_json::from_str(payload).map_err(|e| e.to_string());
+    }
+
+    let bytes = STANDARD.decode(payload).map_err(|e| e.to_string())?;
+    rmp_serde::from_slice(&bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use istanbul_oxide::Range;
+
+    use super::*;
+    use crate::source_coverage::SourceCoverage;
+
+    #[test]
+    fn should_roundtrip_coverage_data() {
+        let mut coverage = SourceCoverage::new("dir/*/file.js".to_string(), true);
+        coverage.new_statement(&Range::new(1, 0, 1, 10));
+        coverage.new_function(
+            &Some("foo".to_string()),
+            &Range::new(2, 9, 2, 12),
+            &Range::new(2, 15, 4, 1),
+        );
+        let branch = coverage.new_branch(
+            istanbul_oxide::BranchType::BinaryExpr,
+            &Range::new(3, 2, 3, 8),
+            true,
+        );
+        coverage.add_branch_path(branch, &Range::new(3, 2, 3, 3));
+        coverage.add_branch_path(branch, &Range::new(3, 7, 3, 8));
+        coverage.freeze();
+
+        for encoding in [MetadataEncoding::Json, MetadataEncoding::MsgpackBase64] {
+            let payload = encode_coverage_data(coverage.as_ref(), encoding);
+            assert!(!payload.contains("*/"));
+
+            let decoded = decode_coverage_data(&payload).expect("Should able to decode");
+            assert_eq!(&decoded, coverage.as_ref(), "{:?}", encoding);
+        }
+    }
+}
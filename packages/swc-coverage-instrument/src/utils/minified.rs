@@ -0,0 +1,21 @@
+use std::sync::Arc;
+
+use swc_core::common::{SourceMapper, Span};
+
+/// Heuristic to determine if given source looks like generated or minified code.
+/// Compares the average line length of the given span against the threshold: a minified
+/// bundle is usually a single (or a handful of) very long lines, which would create
+/// pathological amount of counters for the instrumentation.
+pub fn is_minified<S: SourceMapper>(source_map: &Arc<S>, span: &Span, threshold: u32) -> bool {
+    if span.hi.is_dummy() || span.lo.is_dummy() || span.hi <= span.lo {
+        return false;
+    }
+
+    let span_hi_loc = source_map.lookup_char_pos(span.hi);
+    let span_lo_loc = source_map.lookup_char_pos(span.lo);
+
+    let lines = (span_hi_loc.line - span_lo_loc.line + 1) as u32;
+    let length = (span.hi - span.lo).0;
+
+    length / lines > threshold
+}
@@ -1,3 +1,10 @@
+pub mod comment_positions;
+pub mod concatenated_sources;
+pub mod existing_coverage;
 pub mod hint_comments;
 pub mod lookup_range;
+pub mod metadata_encoding;
+pub mod minified;
 pub mod node;
+pub mod pre_instrumented;
+pub mod trivial_stmt;
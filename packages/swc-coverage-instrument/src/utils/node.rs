@@ -17,6 +17,7 @@ pub enum Node {
     BinExpr,
     LogicalExpr,
     CondExpr,
+    SeqExpr,
     LabeledStmt,
     FnExpr,
     FnDecl,
@@ -30,6 +31,7 @@ pub enum Node {
     ForStmt,
     IfStmt,
     VarDecl,
+    UsingDecl,
     TryStmt,
     ThrowStmt,
     ReturnStmt,
@@ -38,12 +40,17 @@ pub enum Node {
     BreakStmt,
     PrivateProp,
     ClassProp,
+    AutoAccessor,
     ClassDecl,
+    TsEnumDecl,
     ClassMethod,
+    Constructor,
     ExportDecl,
     ExportDefaultDecl,
     BlockStmt,
     AssignPat,
+    AssignPatProp,
+    AssignExpr,
 }
 
 impl Display for Node {
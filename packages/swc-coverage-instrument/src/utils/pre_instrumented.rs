@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+use regex::Regex as Regexp;
+use swc_core::common::{SourceMapper, Span};
+
+static COV_FN_CALL_REGEX: Lazy<Regexp> = Lazy::new(|| Regexp::new(r"\bcov_\w+\s*\(\s*\)").unwrap());
+
+/// Determine if the source text of given span is already instrumented by istanbul compatible
+/// instrumenter, by looking up the coverage fn call along with the coverage schema
+/// or the embedded coverage data comment.
+/// This is checked against the source text before traversal, thus works for the code
+/// instrumented by other tools as well.
+pub fn is_pre_instrumented<S: SourceMapper>(
+    source_map: &Arc<S>,
+    span: &Span,
+    coverage_comment_prefix: &str,
+) -> bool {
+    if span.hi.is_dummy() || span.lo.is_dummy() || span.hi <= span.lo {
+        return false;
+    }
+
+    match source_map.span_to_snippet(*span) {
+        Ok(source) => {
+            COV_FN_CALL_REGEX.is_match(&source)
+                && (source.contains("_coverageSchema") || source.contains(coverage_comment_prefix))
+        }
+        Err(_) => false,
+    }
+}
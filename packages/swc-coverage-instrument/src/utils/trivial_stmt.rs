@@ -0,0 +1,17 @@
+use swc_core::ecma::ast::*;
+
+/// Determine if given statement is trivial enough to omit its statement counter for
+/// `skip_trivial_statements`. The set is kept conservative:
+/// - expression statement of a lone literal, `this` or identifier reference (i.e `a;`)
+/// - unlabeled `break` / `continue` preceded by another statement in the same block, which
+///   executes whenever the preceding statement completes
+pub fn is_trivial_stmt(stmt: &Stmt, has_preceding_stmt: bool) -> bool {
+    match stmt {
+        Stmt::Expr(ExprStmt { expr, .. }) => {
+            matches!(&**expr, Expr::Lit(_) | Expr::This(_) | Expr::Ident(_))
+        }
+        Stmt::Break(BreakStmt { label: None, .. })
+        | Stmt::Continue(ContinueStmt { label: None, .. }) => has_preceding_stmt,
+        _ => false,
+    }
+}
@@ -13,7 +13,10 @@ use crate::{
     instrumentation_stmt_counter_helper, instrumentation_visitor, InstrumentOptions,
 };
 
-create_instrumentation_visitor!(CoverageVisitor { file_path: String });
+create_instrumentation_visitor!(CoverageVisitor {
+    file_path: String,
+    merged_existing: bool
+});
 
 /// Public interface to create a visitor performs transform to inject
 /// coverage instrumentation counter.
@@ -24,38 +27,188 @@ pub fn create_coverage_instrumentation_visitor<C: Clone + Comments, S: SourceMap
     filename: String,
 ) -> CoverageVisitor<C, S> {
     // create a function name ident for the injected coverage instrumentation counters.
-    crate::create_coverage_fn_ident(&filename);
+    let (cov_fn_ident, cov_fn_temp_ident) = crate::create_coverage_fn_ident(&filename);
+
+    let instrument_options = instrument_options.with_preset_applied();
 
     let mut cov = crate::SourceCoverage::new(filename.to_string(), instrument_options.report_logic);
     cov.set_input_source_map(&instrument_options.input_source_map);
+    if let Some(changed_lines) = &instrument_options.changed_lines {
+        cov.set_changed_lines(Some(
+            changed_lines.get(&filename).cloned().unwrap_or_default(),
+        ));
+    }
+    cov.set_instrument_functions(instrument_options.instrument_functions);
+
+    let counter_codegen = std::rc::Rc::new(crate::DefaultCounterCodegen {
+        typed_array_fn_counters: instrument_options.typed_array_fn_counters,
+        increment_style: instrument_options.increment_style,
+    });
 
     CoverageVisitor::new(
         source_map,
         comments.clone(),
         std::rc::Rc::new(std::cell::RefCell::new(cov)),
+        cov_fn_ident,
+        cov_fn_temp_ident,
         instrument_options,
         vec![],
         None,
+        counter_codegen,
+        Default::default(),
         filename,
+        false,
     )
 }
 
+/// Kind of the counters inserted by the instrumentation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CounterKind {
+    Statement,
+    Function,
+    Branch,
+}
+
+/// Performs a dry run of the instrumentation, returns the list of the ranges being tracked by
+/// the coverage counters for the given program. Given program is not modified, as instrumentation
+/// runs against its copy.
+///
+/// Ranges use the same convention as the coverage object (1-based line, 0-based column).
+/// Branches are reported per each branch path.
+pub fn get_instrumented_ranges<C: Clone + Comments, S: SourceMapper>(
+    source_map: std::sync::Arc<S>,
+    comments: C,
+    program: &Program,
+    instrument_options: InstrumentOptions,
+    filename: String,
+) -> Vec<(CounterKind, crate::Range)> {
+    let instrument_options = InstrumentOptions {
+        // Do not attach any comments to the host's comments, nor take the embedded coverage data out of it.
+        debug_initial_coverage_comment: false,
+        emit_summary: false,
+        merge_existing: false,
+        strict_coverage: false,
+        ..instrument_options
+    };
+    let mut visitor =
+        create_coverage_instrumentation_visitor(source_map, comments, instrument_options, filename);
+
+    let mut program = program.clone();
+    program.visit_mut_with(&mut visitor);
+
+    let cov = visitor.cov.borrow();
+    let coverage = cov.as_ref();
+
+    let mut ranges = vec![];
+    ranges.extend(
+        coverage
+            .statement_map
+            .values()
+            .map(|range| (CounterKind::Statement, range.clone())),
+    );
+    ranges.extend(
+        coverage
+            .fn_map
+            .values()
+            .map(|function| (CounterKind::Function, function.loc.clone())),
+    );
+    ranges.extend(coverage.branch_map.values().flat_map(|branch| {
+        branch
+            .locations
+            .iter()
+            .map(|range| (CounterKind::Branch, range.clone()))
+    }));
+
+    ranges
+}
+
 impl<C: Clone + Comments, S: SourceMapper> CoverageVisitor<C, S> {
     instrumentation_counter_helper!();
     instrumentation_stmt_counter_helper!();
 
+    /// Replace the codegen creates counter exprs, to emit custom counters
+    /// instead of istanbul's `cov().s[0]++`. See `CounterCodegen`.
+    pub fn with_counter_codegen(mut self, counter_codegen: Box<dyn crate::CounterCodegen>) -> Self {
+        self.counter_codegen = counter_codegen.into();
+        self
+    }
+
+    /// Returns the coverage data collected by the instrumentation so far.
+    pub fn file_coverage(&self) -> crate::FileCoverage {
+        self.cov.borrow().as_ref().clone()
+    }
+
     /// Not implemented.
     /// TODO: is this required?
     fn is_instrumented_already(&self) -> bool {
         return false;
     }
 
+    /// Import the coverage data embedded by the previous instrumentation if `merge_existing` is enabled.
+    fn merge_existing_coverage(&mut self, span: &Span) {
+        if !self.instrument_options.merge_existing {
+            return;
+        }
+
+        if let Some(existing) = crate::existing_coverage::take_existing_coverage(
+            &self.comments,
+            span,
+            &self.instrument_options.coverage_comment_prefix,
+        ) {
+            self.cov.borrow_mut().import(&existing);
+            self.merged_existing = true;
+        }
+    }
+
+    /// Determine if given stmt is the coverage fn declaration or its initialization call
+    /// injected by the previous instrumentation. Those are replaced by the new coverage template
+    /// when the existing coverage data is merged.
+    fn is_existing_coverage_template(&self, stmt: &Stmt) -> bool {
+        self.merged_existing && self.is_injected_coverage_template(stmt)
+    }
+
+    /// Determine if the number of the counters created exceeds `max_counters` option.
+    fn exceeds_max_counters(&self) -> bool {
+        if let Some(max_counters) = self.instrument_options.max_counters {
+            let cov = self.cov.borrow();
+            let coverage = cov.as_ref();
+            let branches = coverage
+                .branch_map
+                .values()
+                .map(|branch| branch.locations.len())
+                .sum::<usize>();
+            let counters = coverage.statement_map.len() + coverage.fn_map.len() + branches;
+
+            if counters > max_counters as usize {
+                tracing::warn!(
+                    "Skipping instrumentation for {}: {} counters exceed maxCounters {}",
+                    self.file_path,
+                    counters,
+                    max_counters
+                );
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Determine if the file does not have any counters created.
+    fn has_no_counters(&self) -> bool {
+        let cov = self.cov.borrow();
+        let coverage = cov.as_ref();
+        coverage.statement_map.is_empty()
+            && coverage.fn_map.is_empty()
+            && coverage.branch_map.is_empty()
+    }
+
     /// Create coverage instrumentation template exprs to be injected into the top of the transformed output.
-    fn get_coverage_templates(&mut self) -> (Stmt, Stmt) {
+    /// If `coverage_runtime_ident` is given, the coverage fn registers coverage data via the imported
+    /// coverage runtime instead of the inlined global lookup.
+    fn get_coverage_templates(&mut self, coverage_runtime_ident: Option<&Ident>) -> (Stmt, Stmt) {
         self.cov.borrow_mut().freeze();
 
-        //TODO: option: global coverage variable scope. (optional, default `this`)
-        let coverage_global_scope = "this";
+        let coverage_global_scope = self.instrument_options.coverage_global_scope.as_str();
         //TODO: option: use an evaluated function to find coverageGlobalScope.
         let coverage_global_scope_func = true;
 
@@ -84,15 +237,48 @@ impl<C: Clone + Comments, S: SourceMapper> CoverageVisitor<C, S> {
             */
         };
 
-        let coverage_template = crate::create_coverage_fn_decl(
-            &self.instrument_options.coverage_variable,
-            gv_template,
-            &self.cov_fn_ident,
-            &self.file_path,
-            self.cov.borrow().as_ref(),
-            &self.comments,
-            self.instrument_options.debug_initial_coverage_comment,
-        );
+        let coverage_template = match coverage_runtime_ident {
+            Some(runtime_ident) => crate::create_coverage_runtime_fn_decl(
+                runtime_ident,
+                &self.cov_fn_ident,
+                self.cov.borrow().as_ref(),
+                &self.comments,
+                &self.counter_idents,
+                &self.instrument_options,
+            ),
+            None => crate::create_coverage_fn_decl(
+                gv_template,
+                &self.cov_fn_ident,
+                &self.file_path,
+                self.cov.borrow().as_ref(),
+                &self.comments,
+                &self.counter_idents,
+                &self.instrument_options,
+            ),
+        };
+
+        if self.instrument_options.emit_summary {
+            self.comments.add_trailing(
+                Span::dummy_with_cmt().hi,
+                crate::create_coverage_summary_comment(self.cov.borrow().as_ref()),
+            );
+        }
+
+        if self.instrument_options.strict_coverage {
+            let cov = self.cov.borrow();
+            let skipped_nodes = cov.skipped_nodes();
+            if !skipped_nodes.is_empty() {
+                tracing::warn!(
+                    "{} node(s) could not be instrumented: {}",
+                    skipped_nodes.len(),
+                    self.file_path
+                );
+                self.comments.add_trailing(
+                    Span::dummy_with_cmt().hi,
+                    crate::create_skipped_nodes_comment(skipped_nodes),
+                );
+            }
+        }
 
         // explicitly call this.varName to ensure coverage is always initialized
         let call_coverage_template_stmt = Stmt::Expr(ExprStmt {
@@ -117,6 +303,42 @@ impl<C: Clone + Comments, S: SourceMapper> VisitMut for CoverageVisitor<C, S> {
             return;
         }
 
+        if let Some(marker) = &self.instrument_options.include_file_comment {
+            if !crate::hint_comments::should_include_file(&self.comments, program, marker) {
+                return;
+            }
+        }
+
+        if self.instrument_options.skip_if_minified {
+            let span = match &program {
+                Program::Module(module) => module.span,
+                Program::Script(script) => script.span,
+            };
+
+            if crate::minified::is_minified(
+                &self.source_map,
+                &span,
+                self.instrument_options.minified_line_length_threshold,
+            ) {
+                return;
+            }
+        }
+
+        if self.instrument_options.detect_pre_instrumented {
+            let span = match &program {
+                Program::Module(module) => module.span,
+                Program::Script(script) => script.span,
+            };
+
+            if crate::pre_instrumented::is_pre_instrumented(
+                &self.source_map,
+                &span,
+                &self.instrument_options.coverage_comment_prefix,
+            ) {
+                return;
+            }
+        }
+
         if self.is_instrumented_already() {
             return;
         }
@@ -145,10 +367,28 @@ impl<C: Clone + Comments, S: SourceMapper> VisitMut for CoverageVisitor<C, S> {
             self.nodes = new_nodes;
         }
 
+        // Keep the original items to restore if the file exceeds `max_counters`.
+        let original_items = self.instrument_options.max_counters.map(|_| items.clone());
+
+        if let (Some(first), Some(last)) = (items.first(), items.last()) {
+            self.merge_existing_coverage(&Span::new(first.span_lo(), last.span_hi()));
+        }
+
         // TODO: Should module_items need to be added in self.nodes?
         let mut new_items = vec![];
         for mut item in items.drain(..) {
             if let ModuleItem::Stmt(stmt) = &item {
+                if self.is_existing_coverage_template(stmt) {
+                    continue;
+                }
+
+                if self.is_injected_coverage_template(stmt)
+                    || self.is_skipped_trivial_stmt(stmt, !new_items.is_empty())
+                {
+                    new_items.push(item);
+                    continue;
+                }
+
                 // Do not create coverage instrumentation for directives.
                 if stmt.directive_continue() {
                     new_items.push(item);
@@ -162,21 +402,107 @@ impl<C: Clone + Comments, S: SourceMapper> VisitMut for CoverageVisitor<C, S> {
             };
             item.visit_mut_children_with(self);
 
-            new_items.extend(self.before.drain(..).map(|v| ModuleItem::Stmt(v)));
+            let before = self.before.drain(..).collect();
+            new_items.extend(
+                self.coalesce_counter_stmts(before)
+                    .into_iter()
+                    .map(|v| ModuleItem::Stmt(v)),
+            );
             new_items.push(item);
             self.on_exit(old);
         }
         *items = new_items;
 
-        let (coverage_template, call_coverage_template_stmt) = self.get_coverage_templates();
+        if let Some(original_items) = original_items {
+            if self.exceeds_max_counters() {
+                *items = original_items;
+                if !root_exists {
+                    self.nodes.pop();
+                }
+                return;
+            }
+        }
 
-        // prepend template to the top of the code
-        if items.len() >= 1 {
-            items.insert(1, ModuleItem::Stmt(coverage_template));
-            items.insert(2, ModuleItem::Stmt(call_coverage_template_stmt));
-        } else {
-            items.push(ModuleItem::Stmt(coverage_template));
-            items.push(ModuleItem::Stmt(call_coverage_template_stmt));
+        // Do not inject coverage object for the file without executable code, i.e only have imports
+        // or type declarations. Items could be ignored by hint comments, those still need coverage object.
+        if self.has_no_counters() && !items.iter().any(is_executable_module_item) {
+            if !root_exists {
+                self.nodes.pop();
+            }
+            return;
+        }
+
+        // Local ident of the shared coverage runtime's `cov` export, if `coverage_runtime` is set.
+        let coverage_runtime_ident = self.instrument_options.coverage_runtime.as_ref().map(|_| {
+            Ident::new(
+                format!("{}_runtime", self.cov_fn_ident.sym).into(),
+                DUMMY_SP,
+                Default::default(),
+            )
+        });
+        let (coverage_template, call_coverage_template_stmt) =
+            self.get_coverage_templates(coverage_runtime_ident.as_ref());
+
+        // Insert template after import declarations to keep them at the top of the module,
+        // or after directives if there's no import.
+        let insert_idx = items
+            .iter()
+            .rposition(|item| matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))))
+            .map(|idx| idx + 1)
+            .unwrap_or_else(|| {
+                items
+                    .iter()
+                    .take_while(|item| match item {
+                        ModuleItem::Stmt(stmt) => stmt.directive_continue(),
+                        _ => false,
+                    })
+                    .count()
+            });
+        // CommonJS modules can have top-level `return`, everything after it is dead code.
+        // Insert template before the return if it precedes the imports.
+        let insert_idx = match items
+            .iter()
+            .position(|item| matches!(item, ModuleItem::Stmt(Stmt::Return(_))))
+        {
+            Some(return_idx) if return_idx < insert_idx => return_idx,
+            _ => insert_idx,
+        };
+        items.insert(insert_idx, ModuleItem::Stmt(coverage_template));
+        if self.instrument_options.initialize_coverage == crate::InitializeCoverage::Always {
+            items.insert(
+                insert_idx + 1,
+                ModuleItem::Stmt(call_coverage_template_stmt),
+            );
+        }
+
+        // import { cov as cov_xxx_runtime } from "$coverage_runtime";
+        if let (Some(coverage_runtime), Some(coverage_runtime_ident)) = (
+            &self.instrument_options.coverage_runtime,
+            coverage_runtime_ident,
+        ) {
+            items.insert(
+                insert_idx,
+                ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+                    span: DUMMY_SP,
+                    specifiers: vec![ImportSpecifier::Named(ImportNamedSpecifier {
+                        span: DUMMY_SP,
+                        local: coverage_runtime_ident,
+                        imported: Some(ModuleExportName::Ident(Ident::new(
+                            "cov".into(),
+                            DUMMY_SP,
+                            Default::default(),
+                        ))),
+                        is_type_only: false,
+                    })],
+                    src: Box::new(Str {
+                        value: coverage_runtime.as_str().into(),
+                        ..Str::dummy()
+                    }),
+                    type_only: false,
+                    with: None,
+                    phase: Default::default(),
+                })),
+            );
         }
 
         if !root_exists {
@@ -190,19 +516,54 @@ impl<C: Clone + Comments, S: SourceMapper> VisitMut for CoverageVisitor<C, S> {
             return;
         }
 
+        // Keep the original body to restore if the file exceeds `max_counters`.
+        let original_body = self
+            .instrument_options
+            .max_counters
+            .map(|_| items.body.clone());
+
+        self.merge_existing_coverage(&items.span);
+
         let mut new_items = vec![];
         for mut item in items.body.drain(..) {
+            if self.is_existing_coverage_template(&item) {
+                continue;
+            }
+
+            if self.is_injected_coverage_template(&item)
+                || self.is_skipped_trivial_stmt(&item, !new_items.is_empty())
+            {
+                new_items.push(item);
+                continue;
+            }
+
             item.visit_mut_children_with(self);
-            new_items.extend(self.before.drain(..));
+            let before = self.before.drain(..).collect();
+            new_items.extend(self.coalesce_counter_stmts(before));
             new_items.push(item);
         }
         items.body = new_items;
 
-        let (coverage_template, call_coverage_template_stmt) = self.get_coverage_templates();
+        if let Some(original_body) = original_body {
+            if self.exceeds_max_counters() {
+                items.body = original_body;
+                return;
+            }
+        }
+
+        if self.has_no_counters() && !items.body.iter().any(is_executable_stmt) {
+            return;
+        }
 
-        // prepend template to the top of the code
+        // Scripts can't import the coverage runtime, always inline the template.
+        let (coverage_template, call_coverage_template_stmt) = self.get_coverage_templates(None);
+
+        // prepend template to the top of the code, which is always before top-level `return`
+        // of CommonJS modules.
         items.body.insert(0, coverage_template);
-        items.body.insert(1, call_coverage_template_stmt);
+        if self.instrument_options.initialize_coverage == crate::InitializeCoverage::Always {
+            items.body.insert(1, call_coverage_template_stmt);
+        }
     }
 
     // ExportDefaultDeclaration: entries(), // ignore processing only
@@ -232,17 +593,1474 @@ impl<C: Clone + Comments, S: SourceMapper> VisitMut for CoverageVisitor<C, S> {
         }
         self.on_exit(old);
     }
+}
 
-    // DebuggerStatement: entries(coverStatement),
-    #[instrument(skip_all, fields(node = %self.print_node()))]
-    fn visit_mut_debugger_stmt(&mut self, debugger_stmt: &mut DebuggerStmt) {
-        let (old, ignore_current) = self.on_enter(debugger_stmt);
-        match ignore_current {
-            Some(crate::hint_comments::IgnoreScope::Next) => {}
-            _ => {
-                debugger_stmt.visit_mut_children_with(self);
+/// Determine if the declaration emits any runtime code.
+fn is_executable_decl(decl: &Decl) -> bool {
+    match decl {
+        Decl::TsInterface(_) | Decl::TsTypeAlias(_) => false,
+        Decl::Var(var_decl) => !var_decl.declare,
+        Decl::Fn(fn_decl) => !fn_decl.declare,
+        Decl::Class(class_decl) => !class_decl.declare,
+        Decl::TsEnum(ts_enum) => !ts_enum.is_const && !ts_enum.declare,
+        _ => true,
+    }
+}
+
+/// Determine if the statement emits any runtime code.
+fn is_executable_stmt(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Empty(_) => false,
+        Stmt::Decl(decl) => is_executable_decl(decl),
+        _ => true,
+    }
+}
+
+/// Determine if the module item emits any runtime code other than imports / exports.
+fn is_executable_module_item(item: &ModuleItem) -> bool {
+    match item {
+        ModuleItem::Stmt(stmt) => is_executable_stmt(stmt),
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export_decl)) => {
+            is_executable_decl(&export_decl.decl)
+        }
+        ModuleItem::ModuleDecl(
+            ModuleDecl::Import(_)
+            | ModuleDecl::ExportNamed(_)
+            | ModuleDecl::ExportAll(_)
+            | ModuleDecl::TsNamespaceExport(_),
+        ) => false,
+        ModuleItem::ModuleDecl(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_core::{
+        common::{comments::SingleThreadedComments, FileName, SourceMap, GLOBALS},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::{parse_file_as_program, EsSyntax, Syntax, TsSyntax},
+            visit::VisitMutWith,
+        },
+    };
+
+    use super::*;
+
+    fn parse(code: &str) -> (Arc<SourceMap>, SingleThreadedComments, Program) {
+        parse_with_syntax(code, Syntax::Es(Default::default()))
+    }
+
+    fn parse_with_syntax(
+        code: &str,
+        syntax: Syntax,
+    ) -> (Arc<SourceMap>, SingleThreadedComments, Program) {
+        let source_map: Arc<SourceMap> = Default::default();
+        let comments = SingleThreadedComments::default();
+        let fm = source_map.new_source_file(FileName::Anon.into(), code.to_string());
+
+        let program = parse_file_as_program(
+            &fm,
+            syntax,
+            EsVersion::latest(),
+            Some(&comments),
+            &mut vec![],
+        )
+        .expect("Should able to parse");
+
+        (source_map, comments, program)
+    }
+
+    struct Instrumented {
+        visitor: CoverageVisitor<SingleThreadedComments, SourceMap>,
+        program: Program,
+        comments: SingleThreadedComments,
+    }
+
+    /// Parses and instruments the code with the visitor created by `create_visitor`.
+    /// Runs inside of `GLOBALS`, the instrumentation creates spans for the comments it attaches.
+    fn instrument_program_with<F>(code: &str, syntax: Syntax, create_visitor: F) -> Instrumented
+    where
+        F: FnOnce(
+            Arc<SourceMap>,
+            SingleThreadedComments,
+        ) -> CoverageVisitor<SingleThreadedComments, SourceMap>,
+    {
+        GLOBALS.set(&Default::default(), || {
+            let (source_map, comments, program) = parse_with_syntax(code, syntax);
+
+            let mut visitor = create_visitor(source_map, comments.clone());
+            let mut instrumented = program;
+            instrumented.visit_mut_with(&mut visitor);
+
+            Instrumented {
+                visitor,
+                program: instrumented,
+                comments,
+            }
+        })
+    }
+
+    fn instrument_program(
+        code: &str,
+        syntax: Syntax,
+        instrument_options: InstrumentOptions,
+    ) -> Instrumented {
+        instrument_program_with(code, syntax, |source_map, comments| {
+            create_coverage_instrumentation_visitor(
+                source_map,
+                comments,
+                instrument_options,
+                "anon".to_string(),
+            )
+        })
+    }
+
+    fn instrument(
+        code: &str,
+        syntax: Syntax,
+        instrument_options: InstrumentOptions,
+    ) -> (Program, crate::FileCoverage) {
+        let Instrumented {
+            visitor, program, ..
+        } = instrument_program(code, syntax, instrument_options);
+
+        (program, visitor.file_coverage())
+    }
+
+    /// Texts of all the comments, including the ones attached by the instrumentation.
+    fn comment_texts(comments: &SingleThreadedComments) -> Vec<String> {
+        let (leading, trailing) = comments.borrow_all();
+        leading
+            .values()
+            .chain(trailing.values())
+            .flatten()
+            .map(|c| c.text.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn should_report_same_ranges_as_instrumentation() {
+        let code = r#"
+var x = args[0] ? 1 : 2;
+function foo(a = 1) {
+  if (a) {
+    return x || a;
+  }
+}
+output = foo();
+"#;
+        let (source_map, comments, program) = parse(code);
+
+        let ranges = get_instrumented_ranges(
+            source_map.clone(),
+            comments.clone(),
+            &program,
+            Default::default(),
+            "anon".to_string(),
+        );
+        let (_, coverage) = instrument(code, Syntax::Es(Default::default()), Default::default());
+
+        let count = |kind: CounterKind| ranges.iter().filter(|(k, _)| k == &kind).count();
+
+        assert_eq!(count(CounterKind::Statement), coverage.statement_map.len());
+        assert_eq!(count(CounterKind::Function), coverage.fn_map.len());
+        assert_eq!(
+            count(CounterKind::Branch),
+            coverage
+                .branch_map
+                .values()
+                .map(|b| b.locations.len())
+                .sum::<usize>()
+        );
+        assert_eq!(
+            ranges.iter().find(|(k, _)| k == &CounterKind::Statement),
+            Some(&(
+                CounterKind::Statement,
+                coverage.statement_map.get(&0).unwrap().clone()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_not_modify_host_comments_on_dry_run() {
+        let code = r#"var a = 1;
+class A {
+  [a]() {}
+}
+/*__coverage_data_json_comment__::{"path":"anon","statementMap":{},"fnMap":{},"branchMap":{},"s":{},"f":{},"b":{}}*/
+"#;
+        let (source_map, comments, program) = parse(code);
+
+        get_instrumented_ranges(
+            source_map,
+            comments.clone(),
+            &program,
+            InstrumentOptions {
+                merge_existing: true,
+                strict_coverage: true,
+                ..Default::default()
+            },
+            "anon".to_string(),
+        );
+
+        // Neither the embedded coverage data is taken out, nor the skipped nodes are reported.
+        let texts = comment_texts(&comments);
+        assert_eq!(texts.len(), 1);
+        assert!(texts[0].starts_with(crate::COVERAGE_DATA_JSON_COMMENT_PREFIX));
+    }
+
+    #[test]
+    fn should_assign_counter_indices_in_istanbul_order() {
+        // Index to location mapping as emitted by istanbul-lib-instrument for the same input.
+        let code = r#"var a = function () {
+  return 1;
+};
+function b() {
+  var c = a();
+  return c;
+}
+b();
+"#;
+        let (_, coverage) = instrument(code, Syntax::Es(Default::default()), Default::default());
+
+        let statement_starts = (0..coverage.statement_map.len() as u32)
+            .map(|idx| {
+                let start = coverage.statement_map.get(&idx).unwrap().start;
+                (start.line, start.column)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statement_starts,
+            vec![(1, 8), (2, 2), (5, 10), (6, 2), (8, 0)]
+        );
+
+        let fn_starts = (0..coverage.fn_map.len() as u32)
+            .map(|idx| {
+                let start = coverage.fn_map.get(&idx).unwrap().loc.start;
+                (start.line, start.column)
+            })
+            .collect::<Vec<_>>();
+        // istanbul's `fnMap[].loc` is the location of the function body.
+        assert_eq!(fn_starts, vec![(1, 20), (4, 13)]);
+    }
+
+    #[test]
+    fn should_insert_coverage_template_after_imports() {
+        let code = r#""use strict";
+import a from "a";
+console.log(a);
+import { b } from "b";
+console.log(b);
+"#;
+        let Instrumented {
+            visitor, program, ..
+        } = instrument_program(code, Syntax::Es(Default::default()), Default::default());
+
+        let items = match program {
+            Program::Module(module) => module.body,
+            _ => panic!("Should be parsed as module"),
+        };
+
+        let last_import_idx = items
+            .iter()
+            .rposition(|item| matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))))
+            .unwrap();
+
+        match &items[last_import_idx + 1] {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Fn(fn_decl))) => {
+                assert_eq!(fn_decl.ident.sym, visitor.cov_fn_ident.sym);
             }
+            item => panic!("Expected coverage fn decl, got {:?}", item),
         }
-        self.on_exit(old);
+    }
+
+    #[test]
+    fn should_register_coverage_via_coverage_runtime() {
+        let code = r#"import a from "a";
+console.log(a);
+"#;
+        let Instrumented {
+            visitor, program, ..
+        } = instrument_program(
+            code,
+            Syntax::Es(Default::default()),
+            InstrumentOptions {
+                coverage_runtime: Some("@scope/coverage-runtime".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let items = match program {
+            Program::Module(module) => module.body,
+            _ => panic!("Should be parsed as module"),
+        };
+        let runtime_ident = format!("{}_runtime", visitor.cov_fn_ident.sym);
+
+        match &items[1] {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import_decl)) => {
+                assert_eq!(&*import_decl.src.value, "@scope/coverage-runtime");
+                match &import_decl.specifiers[..] {
+                    [ImportSpecifier::Named(ImportNamedSpecifier {
+                        local,
+                        imported: Some(ModuleExportName::Ident(imported)),
+                        ..
+                    })] => {
+                        assert_eq!(&*imported.sym, "cov");
+                        assert_eq!(&*local.sym, runtime_ident);
+                    }
+                    specifiers => panic!("Expected `cov` import, got {:?}", specifiers),
+                }
+            }
+            item => panic!("Expected coverage runtime import, got {:?}", item),
+        }
+
+        let fn_decl = match &items[2] {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Fn(fn_decl))) => fn_decl,
+            item => panic!("Expected coverage fn decl, got {:?}", item),
+        };
+        assert_eq!(fn_decl.ident.sym, visitor.cov_fn_ident.sym);
+
+        // var actualCoverage = cov_xxx_runtime("__coverage__", coverageData);
+        // `create_assignment_stmt` emits the value as the right side of an assign pattern.
+        let stmts = &fn_decl.function.body.as_ref().unwrap().stmts;
+        let registration = stmts.iter().find_map(|stmt| match stmt {
+            Stmt::Decl(Decl::Var(var_decl)) => match &var_decl.decls[0].name {
+                Pat::Assign(AssignPat { right, .. }) => match &**right {
+                    Expr::Call(CallExpr {
+                        callee: Callee::Expr(callee),
+                        args,
+                        ..
+                    }) => Some((callee.clone(), args.len())),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        });
+        match registration {
+            Some((callee, 2)) => match &*callee {
+                Expr::Ident(ident) => assert_eq!(&*ident.sym, runtime_ident),
+                callee => panic!("Expected runtime call, got {:?}", callee),
+            },
+            registration => panic!("Expected runtime registration, got {:?}", registration),
+        }
+        assert!(matches!(
+            &items[3],
+            ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. })) if matches!(&**expr, Expr::Call(_))
+        ));
+    }
+
+    #[test]
+    fn should_exclude_decorators_from_class_ranges() {
+        let code = r#"var A = @dec
+  class {
+    @log
+    foo() {
+      return 1;
+    }
+  };
+"#;
+        let (_, coverage) = instrument(
+            code,
+            Syntax::Es(EsSyntax {
+                decorators: true,
+                ..Default::default()
+            }),
+            Default::default(),
+        );
+
+        let class_start = coverage.statement_map.get(&0).unwrap().start;
+        assert_eq!((class_start.line, class_start.column), (2, 2));
+
+        let method = coverage.fn_map.get(&0).unwrap();
+        assert_eq!(method.name, "foo");
+        assert_eq!((method.decl.start.line, method.decl.start.column), (4, 4));
+    }
+
+    #[test]
+    fn should_cover_auto_accessor_initializer() {
+        let code = r#"class Foo {
+  accessor bar = init();
+}
+new Foo();
+"#;
+        let (_, coverage) = instrument(
+            code,
+            Syntax::Es(EsSyntax {
+                auto_accessors: true,
+                ..Default::default()
+            }),
+            Default::default(),
+        );
+
+        assert_eq!(coverage.statement_map.len(), 2);
+        assert_eq!(
+            coverage.statement_map.get(&0),
+            Some(&crate::Range::new(2, 17, 2, 23))
+        );
+        assert!(coverage.fn_map.is_empty());
+    }
+
+    #[test]
+    fn should_ignore_logical_expr_in_jsx_expr_container() {
+        let code = r#"var el = <div>{/* istanbul ignore next */ cond && <span />}</div>;
+var other = <div>{cond && <span />}</div>;
+"#;
+        let (_, coverage) = instrument(
+            code,
+            Syntax::Es(EsSyntax {
+                jsx: true,
+                ..Default::default()
+            }),
+            Default::default(),
+        );
+
+        assert_eq!(coverage.branch_map.len(), 1);
+        assert_eq!(
+            coverage.branch_map.get(&0).unwrap().locations[0].start.line,
+            2
+        );
+    }
+
+    struct ArrowBodyCollector(Vec<BlockStmtOrExpr>);
+
+    impl swc_core::ecma::visit::Visit for ArrowBodyCollector {
+        fn visit_arrow_expr(&mut self, arrow_expr: &ArrowExpr) {
+            self.0.push(*arrow_expr.body.clone());
+            arrow_expr.visit_children_with(self);
+        }
+    }
+
+    #[test]
+    fn should_convert_expression_bodied_arrow_in_object_property() {
+        let code = "const o = { f: () => doThing() };\n";
+        let (instrumented, coverage) =
+            instrument(code, Syntax::Es(Default::default()), Default::default());
+
+        let mut bodies = ArrowBodyCollector(vec![]);
+        instrumented.visit_with(&mut bodies);
+        assert_eq!(bodies.0.len(), 1);
+
+        match &bodies.0[0] {
+            BlockStmtOrExpr::BlockStmt(block) => {
+                // fn counter, stmt counter of the return stmt, then the return stmt.
+                assert_eq!(block.stmts.len(), 3);
+                assert!(matches!(block.stmts.last(), Some(Stmt::Return(_))));
+            }
+            body => panic!("Should convert the body into block, got {:?}", body),
+        }
+
+        assert_eq!(coverage.fn_map.len(), 1);
+        assert_eq!(coverage.statement_map.len(), 2);
+    }
+
+    #[test]
+    fn should_create_statement_range_for_multi_line_statement() {
+        let code = r#"foo(
+  1,
+  2
+);
+"#;
+        let (_, coverage) = instrument(code, Syntax::Es(Default::default()), Default::default());
+
+        assert_eq!(
+            coverage.statement_map.get(&0),
+            Some(&crate::Range::new(1, 0, 4, 2))
+        );
+    }
+
+    #[test]
+    fn should_escape_embedded_coverage_comment() {
+        let code = "var x = 1;\n";
+        let Instrumented { comments, .. } = instrument_program_with(
+            code,
+            Syntax::Es(Default::default()),
+            |source_map, comments| {
+                create_coverage_instrumentation_visitor(
+                    source_map,
+                    comments,
+                    InstrumentOptions {
+                        debug_initial_coverage_comment: true,
+                        ..Default::default()
+                    },
+                    "src/*/\nfile.js".to_string(),
+                )
+            },
+        );
+
+        let coverage_comments = comment_texts(&comments)
+            .into_iter()
+            .filter(|text| text.starts_with(crate::COVERAGE_DATA_JSON_COMMENT_PREFIX))
+            .collect::<Vec<_>>();
+
+        assert_eq!(coverage_comments.len(), 1);
+
+        let text = &coverage_comments[0];
+        assert!(!text.contains("*/"));
+        assert!(!text.contains('\n'));
+
+        let coverage: serde_json::Value =
+            serde_json::from_str(&text[crate::COVERAGE_DATA_JSON_COMMENT_PREFIX.len()..])
+                .expect("Should able to parse embedded coverage data");
+        assert_eq!(coverage["path"], "src/*/\nfile.js");
+    }
+
+    #[test]
+    fn should_cover_using_declarations() {
+        let code = r#"function f() {
+  using a = open();
+}
+async function g() {
+  await using b = args ? openAsync() : null;
+}
+"#;
+        let (_, coverage) = instrument(
+            code,
+            Syntax::Es(EsSyntax {
+                explicit_resource_management: true,
+                ..Default::default()
+            }),
+            Default::default(),
+        );
+
+        assert_eq!(coverage.fn_map.len(), 2);
+        assert_eq!(
+            coverage.statement_map.get(&0),
+            Some(&crate::Range::new(2, 12, 2, 18))
+        );
+        assert_eq!(
+            coverage.statement_map.get(&1),
+            Some(&crate::Range::new(5, 18, 5, 43))
+        );
+        assert_eq!(coverage.branch_map.len(), 1);
+    }
+
+    #[test]
+    fn should_not_instrument_file_exceeds_max_counters() {
+        let code = r#"var a = 1;
+var b = args[0] ? 1 : 2;
+function foo() {
+  return a + b;
+}
+"#;
+        let (_, _, program) = parse(code);
+        let (instrumented, _) = instrument(
+            code,
+            Syntax::Es(Default::default()),
+            InstrumentOptions {
+                max_counters: Some(3),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(instrumented, program);
+    }
+
+    #[test]
+    fn should_not_create_fn_counters_for_re_exports() {
+        let code = r#"export { a } from "a";
+export * as b from "b";
+const c = 1;
+export { c as default };
+export function foo() {
+  return c;
+}
+"#;
+        let (_, coverage) = instrument(code, Syntax::Es(Default::default()), Default::default());
+
+        let fn_names = coverage
+            .fn_map
+            .values()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(fn_names, vec!["foo"]);
+        assert_eq!(coverage.statement_map.len(), 2);
+    }
+
+    #[test]
+    fn should_not_inject_coverage_object_without_executable_code() {
+        let code = r#"import { a } from "a";
+import type { B } from "b";
+export { c } from "c";
+export interface D {
+  e: B;
+}
+type F = string;
+"#;
+        let (_, _, program) = parse_with_syntax(code, Syntax::Typescript(TsSyntax::default()));
+        let (instrumented, _) = instrument(
+            code,
+            Syntax::Typescript(TsSyntax::default()),
+            Default::default(),
+        );
+
+        assert_eq!(instrumented, program);
+    }
+
+    #[test]
+    fn should_not_count_type_alias_and_interface() {
+        let code = r#"type A = string;
+const a: A = "a";
+function foo() {
+  type B = number;
+  interface C { c: B }
+  const b: B = 1;
+  return b;
+}
+interface D {}
+foo();
+"#;
+        let (_, coverage) = instrument(
+            code,
+            Syntax::Typescript(TsSyntax::default()),
+            Default::default(),
+        );
+
+        let statement_starts = (0..coverage.statement_map.len() as u32)
+            .map(|idx| {
+                let start = coverage.statement_map.get(&idx).unwrap().start;
+                (start.line, start.column)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(statement_starts, vec![(2, 13), (6, 15), (7, 2), (10, 0)]);
+    }
+
+    struct TsTypeCollector(Vec<TsType>);
+
+    impl swc_core::ecma::visit::Visit for TsTypeCollector {
+        fn visit_ts_type(&mut self, ty: &TsType) {
+            self.0.push(ty.clone());
+        }
+    }
+
+    #[test]
+    fn should_not_inject_counters_into_type_positions() {
+        let code = r#"const x = (foo() as Bar);
+const y = (args[0] ? a : b) satisfies Baz<string>;
+const z = <Qux>(c || d);
+const w = foo<Bar>()!;
+"#;
+        let (_, _, program) = parse_with_syntax(code, Syntax::Typescript(TsSyntax::default()));
+        let (instrumented, coverage) = instrument(
+            code,
+            Syntax::Typescript(TsSyntax::default()),
+            Default::default(),
+        );
+
+        // Runtime exprs under the type assertions are still covered.
+        assert_eq!(coverage.statement_map.len(), 4);
+        assert_eq!(coverage.branch_map.len(), 2);
+
+        let mut original_types = TsTypeCollector(vec![]);
+        program.visit_with(&mut original_types);
+        let mut instrumented_types = TsTypeCollector(vec![]);
+        instrumented.visit_with(&mut instrumented_types);
+
+        assert_eq!(original_types.0.len(), 4);
+        assert_eq!(instrumented_types.0, original_types.0);
+    }
+
+    struct CoverageObjectPropCollector(Vec<(String, String)>);
+
+    impl swc_core::ecma::visit::Visit for CoverageObjectPropCollector {
+        fn visit_key_value_prop(&mut self, prop: &KeyValueProp) {
+            if let (PropName::Ident(key), Expr::Lit(Lit::Str(value))) = (&prop.key, &*prop.value) {
+                if matches!(&*key.sym, "_coverageSchema" | "hash") {
+                    self.0.push((key.sym.to_string(), value.value.to_string()));
+                }
+            }
+            prop.visit_children_with(self);
+        }
+    }
+
+    fn collect_coverage_object_schema(code: &str, filename: &str) -> Vec<(String, String)> {
+        let Instrumented { program, .. } = instrument_program_with(
+            code,
+            Syntax::Es(Default::default()),
+            |source_map, comments| {
+                create_coverage_instrumentation_visitor(
+                    source_map,
+                    comments,
+                    Default::default(),
+                    filename.to_string(),
+                )
+            },
+        );
+
+        let mut collector = CoverageObjectPropCollector(vec![]);
+        program.visit_with(&mut collector);
+        collector.0
+    }
+
+    #[test]
+    fn should_instrument_constructor_with_parameter_properties() {
+        let code = r#"class Foo {
+  constructor(private x = compute()) {}
+}
+new Foo();
+"#;
+        let (_, coverage) = instrument(
+            code,
+            Syntax::Typescript(TsSyntax::default()),
+            Default::default(),
+        );
+
+        let constructor = coverage.fn_map.get(&0).unwrap();
+        assert_eq!(constructor.name, "constructor");
+        assert_eq!(
+            (constructor.decl.start.line, constructor.decl.start.column),
+            (2, 2)
+        );
+
+        assert_eq!(coverage.branch_map.len(), 1);
+        let branch = coverage.branch_map.get(&0).unwrap();
+        assert_eq!(branch.branch_type, crate::BranchType::DefaultArg);
+        assert_eq!(branch.locations.len(), 1);
+
+        assert_eq!(coverage.statement_map.len(), 1);
+    }
+
+    #[test]
+    fn should_skip_const_and_ambient_enums() {
+        let code = r#"enum Color {
+  Red = compute() ? 1 : 2,
+}
+export const enum Direction {
+  Up = 1,
+}
+declare enum Ambient {
+  A,
+}
+"#;
+        let (_, coverage) = instrument(
+            code,
+            Syntax::Typescript(TsSyntax::default()),
+            Default::default(),
+        );
+
+        assert_eq!(coverage.statement_map.len(), 1);
+        assert_eq!(
+            coverage.statement_map.get(&0),
+            Some(&crate::Range::new(1, 0, 3, 1))
+        );
+        assert_eq!(coverage.branch_map.len(), 1);
+    }
+
+    #[test]
+    fn should_use_operand_location_for_logical_branch_paths() {
+        let code = r#"var x = first && second && third;
+var y = first && (second ? 1 : 2);
+"#;
+        let (_, coverage) = instrument(code, Syntax::Es(Default::default()), Default::default());
+
+        let branch = coverage.branch_map.get(&0).unwrap();
+        assert_eq!(branch.loc, Some(crate::Range::new(1, 8, 1, 32)));
+        assert_eq!(
+            branch.locations,
+            vec![
+                crate::Range::new(1, 8, 1, 13),
+                crate::Range::new(1, 17, 1, 23),
+                crate::Range::new(1, 27, 1, 32),
+            ]
+        );
+
+        // Parens around the operand are not part of its location.
+        let branch = coverage.branch_map.get(&1).unwrap();
+        assert_eq!(
+            branch.locations,
+            vec![
+                crate::Range::new(2, 8, 2, 13),
+                crate::Range::new(2, 18, 2, 32),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_insert_coverage_template_before_top_level_return() {
+        for code in [
+            "\"use strict\";\nif (process.env.SKIP) {}\nreturn;\nmodule.exports = 1;\n",
+            "return;\nimport a from \"a\";\nconsole.log(a);\n",
+        ] {
+            let Instrumented {
+                visitor, program, ..
+            } = instrument_program(
+                code,
+                Syntax::Es(EsSyntax {
+                    allow_return_outside_function: true,
+                    ..Default::default()
+                }),
+                InstrumentOptions {
+                    initialize_coverage: crate::InitializeCoverage::Always,
+                    ..Default::default()
+                },
+            );
+
+            let stmts: Vec<&Stmt> = match &program {
+                Program::Module(module) => module
+                    .body
+                    .iter()
+                    .filter_map(|item| match item {
+                        ModuleItem::Stmt(stmt) => Some(stmt),
+                        _ => None,
+                    })
+                    .collect(),
+                Program::Script(script) => script.body.iter().collect(),
+            };
+            let return_idx = stmts
+                .iter()
+                .position(|stmt| matches!(stmt, Stmt::Return(_)))
+                .expect("Should have top-level return");
+
+            // Both of the coverage fn declaration and its initial call precede the return.
+            assert_eq!(
+                stmts[..return_idx]
+                    .iter()
+                    .filter(|stmt| visitor.is_injected_coverage_template(stmt))
+                    .count(),
+                2
+            );
+        }
+    }
+
+    #[test]
+    fn should_emit_coverage_schema_and_per_file_hash() {
+        let foo = collect_coverage_object_schema("var x = 1;", "foo.js");
+        let bar = collect_coverage_object_schema("var y = 2;\nvar z = 3;", "bar.js");
+
+        assert_eq!(foo.len(), 2);
+        assert_eq!(
+            foo[0],
+            (
+                "_coverageSchema".to_string(),
+                crate::COVERAGE_MAGIC_VALUE.to_string()
+            )
+        );
+        assert_eq!(foo[1].0, "hash");
+        assert!(!foo[1].1.is_empty());
+
+        assert_eq!(bar[0], foo[0]);
+        assert_eq!(bar[1].0, "hash");
+        assert_ne!(bar[1].1, foo[1].1);
+    }
+
+    #[test]
+    fn should_instrument_only_changed_lines() {
+        let code = r#"var a = 1;
+function foo(x) {
+  var b = x ? 1 : 2;
+  return b;
+}
+var c = a || 3;
+foo(a);
+"#;
+        let (_, coverage) = instrument(
+            code,
+            Syntax::Es(Default::default()),
+            InstrumentOptions {
+                changed_lines: Some(
+                    [("anon".to_string(), vec![(3, 3), (7, 7)])]
+                        .into_iter()
+                        .collect(),
+                ),
+                ..Default::default()
+            },
+        );
+
+        let statement_starts = (0..coverage.statement_map.len() as u32)
+            .map(|idx| {
+                let start = coverage.statement_map.get(&idx).unwrap().start;
+                (start.line, start.column)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(statement_starts, vec![(3, 10), (7, 0)]);
+
+        let fn_names = coverage
+            .fn_map
+            .values()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(fn_names, vec!["foo"]);
+
+        let branch_types = coverage
+            .branch_map
+            .values()
+            .map(|b| b.branch_type.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(branch_types, vec![crate::BranchType::CondExpr]);
+        assert_eq!(coverage.b.get(&0).map(|b| b.len()), Some(2));
+    }
+
+    #[test]
+    fn should_apply_mutation_preset() {
+        let code = r#"function foo(x) {
+  return x ? 1 : 2;
+}
+var bar = () => foo(1) || 3;
+bar();
+"#;
+        let Instrumented { visitor, .. } = instrument_program(
+            code,
+            Syntax::Es(Default::default()),
+            InstrumentOptions {
+                preset: Some(crate::InstrumentPreset::Mutation),
+                ..Default::default()
+            },
+        );
+
+        assert!(visitor.instrument_options.compact);
+        assert!(!visitor.instrument_options.instrument_functions);
+        assert_eq!(
+            visitor.instrument_options.initialize_coverage,
+            crate::InitializeCoverage::Lazy
+        );
+
+        let cov = visitor.cov.borrow();
+        let coverage = cov.as_ref();
+        assert!(coverage.fn_map.is_empty());
+        assert!(coverage.f.is_empty());
+        assert_eq!(coverage.statement_map.len(), 4);
+        assert_eq!(coverage.branch_map.len(), 2);
+    }
+
+    #[test]
+    fn should_coalesce_adjacent_counters() {
+        let code = r#"var a = function () {}, b = function () {};
+a(b());
+"#;
+        let instrument_script = |coalesce_counters: bool| {
+            let (instrumented, coverage) = instrument(
+                code,
+                Syntax::Es(Default::default()),
+                InstrumentOptions {
+                    coalesce_counters,
+                    ..Default::default()
+                },
+            );
+            let items = match instrumented {
+                Program::Script(script) => script.body,
+                _ => panic!("Should be parsed as script"),
+            };
+            (coverage, items)
+        };
+
+        let (coalesced_coverage, coalesced) = instrument_script(true);
+        let (plain_coverage, plain) = instrument_script(false);
+
+        assert_eq!(coalesced_coverage, plain_coverage);
+        assert_eq!(coalesced.len(), plain.len() - 1);
+        assert!(coalesced.iter().any(|stmt| matches!(
+            stmt,
+            Stmt::Expr(ExprStmt { expr, .. })
+                if matches!(&**expr, Expr::Seq(SeqExpr { exprs, .. }) if exprs.len() == 2)
+        )));
+    }
+
+    #[test]
+    fn should_emit_increments_in_given_increment_style() {
+        let counter_expr = |increment_style: crate::IncrementStyle| {
+            let (instrumented, _) = instrument(
+                "foo();\n",
+                Syntax::Es(Default::default()),
+                InstrumentOptions {
+                    increment_style,
+                    ..Default::default()
+                },
+            );
+
+            let mut items = match instrumented {
+                Program::Script(script) => script.body,
+                _ => panic!("Should be parsed as script"),
+            };
+            // Counter is prepended to the last statement, `foo();`.
+            items.pop();
+            match items.pop() {
+                Some(Stmt::Expr(ExprStmt { expr, .. })) => *expr,
+                stmt => panic!("Should be a counter stmt, got {:?}", stmt),
+            }
+        };
+
+        assert!(matches!(
+            counter_expr(crate::IncrementStyle::Increment),
+            Expr::Update(UpdateExpr {
+                op: UpdateOp::PlusPlus,
+                prefix: false,
+                ..
+            })
+        ));
+
+        match counter_expr(crate::IncrementStyle::Assign) {
+            Expr::Assign(AssignExpr {
+                op: AssignOp::Assign,
+                left: AssignTarget::Simple(SimpleAssignTarget::Member(counter)),
+                right,
+                ..
+            }) => match *right {
+                Expr::Bin(BinExpr {
+                    op: BinaryOp::Add,
+                    left,
+                    right,
+                    ..
+                }) => {
+                    assert_eq!(*left, Expr::Member(counter));
+                    assert!(
+                        matches!(*right, Expr::Lit(Lit::Num(Number { value, .. })) if value == 1.0)
+                    );
+                }
+                expr => panic!("Should read the counter, got {:?}", expr),
+            },
+            expr => panic!("Should assign the counter, got {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn should_report_skipped_nodes_with_strict_coverage() {
+        let code = r#"var key = "foo";
+class A {
+  [key]() {
+    return 1;
+  }
+  bar() {
+    return 2;
+  }
+}
+"#;
+        let skipped_comments = |strict_coverage: bool| {
+            let Instrumented { comments, .. } = instrument_program(
+                code,
+                Syntax::Es(Default::default()),
+                InstrumentOptions {
+                    strict_coverage,
+                    ..Default::default()
+                },
+            );
+
+            comment_texts(&comments)
+                .into_iter()
+                .filter(|text| text.starts_with(crate::COVERAGE_SKIPPED_COMMENT_PREFIX))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            skipped_comments(true),
+            vec![format!(
+                "{}class method with non-identifier key (3:2)",
+                crate::COVERAGE_SKIPPED_COMMENT_PREFIX
+            )]
+        );
+        assert!(skipped_comments(false).is_empty());
+    }
+
+    #[test]
+    fn should_skip_functions_without_body() {
+        let code = r#"function foo(a: string): string;
+function foo(a: number): number;
+function foo(a: any) {
+  return a;
+}
+"#;
+        let Instrumented { visitor, .. } = instrument_program(
+            code,
+            Syntax::Typescript(TsSyntax::default()),
+            InstrumentOptions {
+                strict_coverage: true,
+                ..Default::default()
+            },
+        );
+
+        // Overload signatures are erased by the compilation, not skipped by the instrumentation.
+        let cov = visitor.cov.borrow();
+        assert_eq!(cov.as_ref().fn_map.len(), 1);
+        assert!(cov.skipped_nodes().is_empty());
+    }
+
+    #[test]
+    fn should_inject_coverage_object_for_ignored_code() {
+        let code = r#"/* istanbul ignore next */
+foo();
+"#;
+        let (_, _, program) = parse(code);
+        let (instrumented, _) =
+            instrument(code, Syntax::Es(Default::default()), Default::default());
+
+        assert_ne!(instrumented, program);
+    }
+
+    #[test]
+    fn should_not_instrument_pre_instrumented_code() {
+        let code = r#"function cov_1234() {
+  var coverageData = { _coverageSchema: "1a1c01bbd47fc00a2c39e90264f33305ed495a6d" };
+  return coverageData;
+}
+cov_1234();
+cov_1234().s[0]++;
+var x = 1;
+"#;
+        let (_, _, program) = parse(code);
+        let (instrumented, _) = instrument(
+            code,
+            Syntax::Es(Default::default()),
+            InstrumentOptions {
+                detect_pre_instrumented: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(instrumented, program);
+    }
+
+    #[test]
+    fn should_add_branch_paths_in_source_order() {
+        let code = r#"var a = args[0] || args[1] && args[2];
+var b = args[0] ? args[1] : args[2];
+if (a) {
+  b = 1;
+} else {
+  b = 2;
+}
+"#;
+        let (_, coverage) = instrument(code, Syntax::Es(Default::default()), Default::default());
+
+        let locations = |idx: u32| {
+            coverage
+                .branch_map
+                .get(&idx)
+                .unwrap()
+                .locations
+                .iter()
+                .map(|loc| (loc.start.line, loc.start.column, loc.end.column))
+                .collect::<Vec<_>>()
+        };
+
+        // Binary expr: left, then right operands in source order, flattening nested logical exprs.
+        assert_eq!(locations(0), vec![(1, 8, 15), (1, 19, 26), (1, 30, 37)]);
+        // Conditional expr: consequent, then alternate.
+        assert_eq!(locations(1), vec![(2, 18, 25), (2, 28, 35)]);
+        // If: both paths point to the if statement, consequent is the first path.
+        assert_eq!(locations(2), vec![(3, 0, 1), (3, 0, 1)]);
+    }
+
+    #[test]
+    fn should_count_and_preserve_debugger_stmt() {
+        let code = r#"function foo() {
+  debugger;
+}
+debugger;
+"#;
+        let (instrumented, coverage) =
+            instrument(code, Syntax::Es(Default::default()), Default::default());
+
+        assert_eq!(
+            coverage.statement_map.get(&0),
+            Some(&crate::Range::new(2, 2, 2, 11))
+        );
+        assert_eq!(
+            coverage.statement_map.get(&1),
+            Some(&crate::Range::new(4, 0, 4, 9))
+        );
+
+        let items = match instrumented {
+            Program::Script(script) => script.body,
+            _ => panic!("Should be parsed as script"),
+        };
+        let fn_body = items
+            .iter()
+            .find_map(|stmt| match stmt {
+                Stmt::Decl(Decl::Fn(fn_decl)) if &*fn_decl.ident.sym == "foo" => {
+                    fn_decl.function.body.as_ref()
+                }
+                _ => None,
+            })
+            .expect("Should have fn foo");
+
+        assert!(matches!(fn_body.stmts.last(), Some(Stmt::Debugger(_))));
+        assert!(matches!(items.last(), Some(Stmt::Debugger(_))));
+    }
+
+    struct CounterCollector<'a> {
+        cov_fn_ident: &'a Ident,
+        count: usize,
+    }
+
+    impl swc_core::ecma::visit::Visit for CounterCollector<'_> {
+        fn visit_expr(&mut self, expr: &Expr) {
+            let codegen = crate::DefaultCounterCodegen::default();
+            if crate::CounterCodegen::is_counter_expr(&codegen, self.cov_fn_ident, expr) {
+                self.count += 1;
+            }
+            expr.visit_children_with(self);
+        }
+    }
+
+    #[test]
+    fn should_not_instrument_injected_coverage_fn() {
+        let Instrumented {
+            visitor,
+            program: mut instrumented,
+            comments,
+        } = instrument_program(
+            "foo();\n",
+            Syntax::Es(Default::default()),
+            Default::default(),
+        );
+
+        // Instrument the output again, i.e the pass running twice.
+        let mut visitor = create_coverage_instrumentation_visitor(
+            visitor.source_map.clone(),
+            comments,
+            Default::default(),
+            "anon".to_string(),
+        );
+        GLOBALS.set(&Default::default(), || {
+            instrumented.visit_mut_with(&mut visitor)
+        });
+
+        let cov = visitor.cov.borrow();
+        let coverage = cov.as_ref();
+        assert_eq!(coverage.statement_map.len(), 1);
+        assert!(coverage.fn_map.is_empty());
+
+        let items = match &instrumented {
+            Program::Script(script) => &script.body,
+            _ => panic!("Should be parsed as script"),
+        };
+        let cov_fn_decls: Vec<&FnDecl> = items
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Decl(Decl::Fn(fn_decl)) if fn_decl.ident.sym == visitor.cov_fn_ident.sym => {
+                    Some(fn_decl)
+                }
+                _ => None,
+            })
+            .collect();
+        assert!(!cov_fn_decls.is_empty());
+
+        for fn_decl in cov_fn_decls {
+            let mut counters = CounterCollector {
+                cov_fn_ident: &visitor.cov_fn_ident,
+                count: 0,
+            };
+            fn_decl.visit_with(&mut counters);
+            assert_eq!(counters.count, 0);
+        }
+    }
+
+    #[test]
+    fn should_merge_existing_coverage() {
+        let code = r#"var a = 1;
+var b = args[0] ? 1 : 2;
+/*__coverage_data_json_comment__::{"path":"anon","statementMap":{"0":{"start":{"line":10,"column":0},"end":{"line":10,"column":8}}},"fnMap":{},"branchMap":{},"s":{"0":2},"f":{},"b":{}}*/
+"#;
+        let Instrumented {
+            visitor, comments, ..
+        } = instrument_program(
+            code,
+            Syntax::Es(Default::default()),
+            InstrumentOptions {
+                merge_existing: true,
+                ..Default::default()
+            },
+        );
+
+        let cov = visitor.cov.borrow();
+        let coverage = cov.as_ref();
+
+        // Existing statement keeps its index, new statements are appended.
+        assert_eq!(coverage.statement_map.len(), 3);
+        assert_eq!(
+            coverage.statement_map.get(&0),
+            Some(&crate::Range::new(10, 0, 10, 8))
+        );
+        assert_eq!(coverage.s.get(&0), Some(&2));
+        assert_eq!(
+            coverage.statement_map.get(&1),
+            Some(&crate::Range::new(1, 8, 1, 9))
+        );
+        assert_eq!(coverage.branch_map.len(), 1);
+
+        // Previous coverage data comment is replaced.
+        assert!(!comment_texts(&comments)
+            .iter()
+            .any(|text| text.starts_with(crate::COVERAGE_DATA_JSON_COMMENT_PREFIX)));
+    }
+
+    #[test]
+    fn should_emit_report_logic_coverage_conforms_file_coverage_schema() {
+        let code = r#"function foo(a = 1, { b = 2 } = {}) {
+  var x = a && (b || args[0]) ? a ?? b : args[1] || args[2];
+  if (x) {
+    switch (x) {
+      case 1:
+        return a || b;
+      default:
+        return x;
+    }
+  }
+}
+output = foo(args[0] && args[1]);
+"#;
+        let Instrumented { comments, .. } = instrument_program(
+            code,
+            Syntax::Es(Default::default()),
+            InstrumentOptions {
+                report_logic: true,
+                debug_initial_coverage_comment: true,
+                ..Default::default()
+            },
+        );
+
+        let text = comment_texts(&comments)
+            .into_iter()
+            .find(|text| text.starts_with(crate::COVERAGE_DATA_JSON_COMMENT_PREFIX))
+            .expect("Should have embedded coverage data");
+        let json = &text[crate::COVERAGE_DATA_JSON_COMMENT_PREFIX.len()..];
+
+        // Emitted JSON should be deserializable as a file coverage.
+        let coverage: crate::FileCoverage =
+            serde_json::from_str(json).expect("Should able to deserialize file coverage");
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+
+        for key in [
+            "path",
+            "statementMap",
+            "fnMap",
+            "branchMap",
+            "s",
+            "f",
+            "b",
+            "bT",
+        ] {
+            assert!(value.get(key).is_some(), "Should have `{}`", key);
+        }
+
+        assert_eq!(
+            coverage.statement_map.keys().collect::<Vec<_>>(),
+            coverage.s.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            coverage.fn_map.keys().collect::<Vec<_>>(),
+            coverage.f.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            coverage.branch_map.keys().collect::<Vec<_>>(),
+            coverage.b.keys().collect::<Vec<_>>()
+        );
+
+        let b_t = coverage
+            .b_t
+            .as_ref()
+            .expect("Should have bT with reportLogic");
+        assert!(!b_t.is_empty());
+        for (key, branch) in &coverage.branch_map {
+            let counts = coverage.b.get(key).unwrap();
+            assert!(!branch.locations.is_empty());
+            assert_eq!(branch.locations.len(), counts.len(), "branch {}", key);
+
+            // Only logical expr branches track truthy counts.
+            match b_t.get(key) {
+                Some(counts_true) => {
+                    assert_eq!(branch.branch_type, crate::BranchType::BinaryExpr);
+                    assert_eq!(counts_true.len(), counts.len(), "branch {}", key);
+                }
+                None => assert_ne!(branch.branch_type, crate::BranchType::BinaryExpr),
+            }
+        }
+    }
+
+    struct HitCounterCodegen;
+
+    impl HitCounterCodegen {
+        fn create_hit(&self, id: String) -> Expr {
+            Expr::Call(CallExpr {
+                callee: Callee::Expr(Box::new(Expr::Ident(Ident {
+                    sym: "__hit".into(),
+                    ..Ident::dummy()
+                }))),
+                args: vec![ExprOrSpread {
+                    spread: None,
+                    expr: Box::new(Expr::Lit(Lit::Str(id.into()))),
+                }],
+                ..CallExpr::dummy()
+            })
+        }
+    }
+
+    impl crate::CounterCodegen for HitCounterCodegen {
+        fn statement_increment(&self, _: &Ident, _: &Ident, id: u32) -> Expr {
+            self.create_hit(format!("s{}", id))
+        }
+
+        fn branch_increment(&self, _: &Ident, _: &Ident, id: u32, idx: u32) -> Expr {
+            self.create_hit(format!("b{}_{}", id, idx))
+        }
+
+        fn function_increment(&self, _: &Ident, _: &Ident, id: u32) -> Expr {
+            self.create_hit(format!("f{}", id))
+        }
+
+        fn is_counter_expr(&self, _: &Ident, expr: &Expr) -> bool {
+            match expr {
+                Expr::Call(CallExpr {
+                    callee: Callee::Expr(callee),
+                    ..
+                }) => matches!(&**callee, Expr::Ident(ident) if &*ident.sym == "__hit"),
+                _ => false,
+            }
+        }
+    }
+
+    struct HitCollector(Vec<String>);
+
+    impl swc_core::ecma::visit::Visit for HitCollector {
+        fn visit_call_expr(&mut self, call_expr: &CallExpr) {
+            let is_hit = matches!(
+                &call_expr.callee,
+                Callee::Expr(callee) if matches!(&**callee, Expr::Ident(ident) if &*ident.sym == "__hit")
+            );
+            if is_hit {
+                if let Some(Expr::Lit(Lit::Str(id))) = call_expr.args.first().map(|arg| &*arg.expr)
+                {
+                    self.0.push(id.value.to_string());
+                }
+            }
+            call_expr.visit_children_with(self);
+        }
+    }
+
+    #[test]
+    fn should_emit_counters_via_custom_counter_codegen() {
+        let code = r#"function foo(a) {
+  return a ? 1 : 2;
+}
+foo(true);
+"#;
+        let Instrumented {
+            visitor, program, ..
+        } = instrument_program_with(
+            code,
+            Syntax::Es(Default::default()),
+            |source_map, comments| {
+                create_coverage_instrumentation_visitor(
+                    source_map,
+                    comments,
+                    Default::default(),
+                    "anon".to_string(),
+                )
+                .with_counter_codegen(Box::new(HitCounterCodegen))
+            },
+        );
+
+        let mut hits = HitCollector(vec![]);
+        program.visit_with(&mut hits);
+        hits.0.sort();
+
+        assert_eq!(hits.0, vec!["b0_0", "b0_1", "f0", "s0", "s1"]);
+
+        let cov = visitor.cov.borrow();
+        let coverage = cov.as_ref();
+        assert_eq!(coverage.statement_map.len(), 2);
+        assert_eq!(coverage.fn_map.len(), 1);
+        assert_eq!(coverage.branch_map.len(), 1);
     }
 }
@@ -22,6 +22,7 @@ impl Visit for HoistingFinder {
         self.0 = true;
     }
 
+    // Static initializers run when a class is created.
     fn visit_class_expr(&mut self, _class_expr: &ClassExpr) {
         self.0 = true;
     }
@@ -92,4 +93,101 @@ impl Visit for LogicalExprLeafFinder {
 
         bin_expr.visit_children_with(self);
     }
+
+    // Conditional expr is a leaf, logical exprs inside of it belong to its own branch.
+    // They are registered when the leaf is visited (`a && (b ? c || d : e)`).
+    fn visit_cond_expr(&mut self, _cond_expr: &CondExpr) {}
+}
+
+/// Check if nodes may interrupt the straight-line execution, i.e calls / member accesses
+/// which can throw, or expressions create branches.
+/// This is a heuristic, not a guarantee: operators can still invoke user code via coercion
+/// or throw (mixing BigInt with Number), as well as the identifier references (undeclared names,
+/// the temporal dead zone, assignment to an undeclared name in strict mode).
+#[derive(Debug)]
+pub struct InterruptionFinder(pub bool);
+
+impl InterruptionFinder {
+    pub fn new() -> InterruptionFinder {
+        InterruptionFinder(false)
+    }
+}
+
+impl Visit for InterruptionFinder {
+    fn visit_call_expr(&mut self, _call_expr: &CallExpr) {
+        self.0 = true;
+    }
+
+    fn visit_new_expr(&mut self, _new_expr: &NewExpr) {
+        self.0 = true;
+    }
+
+    fn visit_member_expr(&mut self, _member_expr: &MemberExpr) {
+        self.0 = true;
+    }
+
+    fn visit_super_prop_expr(&mut self, _super_prop_expr: &SuperPropExpr) {
+        self.0 = true;
+    }
+
+    fn visit_opt_chain_expr(&mut self, _opt_chain_expr: &OptChainExpr) {
+        self.0 = true;
+    }
+
+    fn visit_tagged_tpl(&mut self, _tagged_tpl: &TaggedTpl) {
+        self.0 = true;
+    }
+
+    fn visit_cond_expr(&mut self, _cond_expr: &CondExpr) {
+        self.0 = true;
+    }
+
+    fn visit_await_expr(&mut self, _await_expr: &AwaitExpr) {
+        self.0 = true;
+    }
+
+    fn visit_yield_expr(&mut self, _yield_expr: &YieldExpr) {
+        self.0 = true;
+    }
+
+    fn visit_pat(&mut self, pat: &Pat) {
+        // Destructuring invokes iterators / getters.
+        match pat {
+            Pat::Ident(_) | Pat::Expr(_) => pat.visit_children_with(self),
+            _ => self.0 = true,
+        }
+    }
+
+    fn visit_spread_element(&mut self, _spread_element: &SpreadElement) {
+        self.0 = true;
+    }
+
+    fn visit_expr_or_spread(&mut self, expr_or_spread: &ExprOrSpread) {
+        if expr_or_spread.spread.is_some() {
+            self.0 = true;
+        } else {
+            expr_or_spread.visit_children_with(self);
+        }
+    }
+
+    fn visit_bin_expr(&mut self, bin_expr: &BinExpr) {
+        match bin_expr.op {
+            BinaryOp::LogicalOr
+            | BinaryOp::LogicalAnd
+            | BinaryOp::NullishCoalescing
+            | BinaryOp::In
+            | BinaryOp::InstanceOf => self.0 = true,
+            _ => bin_expr.visit_children_with(self),
+        }
+    }
+
+    // Creating a function does not run its body.
+    fn visit_fn_expr(&mut self, _fn_expr: &FnExpr) {}
+
+    fn visit_arrow_expr(&mut self, _arrow_expr: &ArrowExpr) {}
+
+    // Static initializers run when a class is created.
+    fn visit_class_expr(&mut self, _class_expr: &ClassExpr) {
+        self.0 = true;
+    }
 }
@@ -8,7 +8,7 @@ use swc_core::{
 use tracing::instrument;
 
 use crate::{
-    constants::idents::IDENT_B, create_instrumentation_visitor, instrumentation_counter_helper,
+    create_instrumentation_visitor, instrumentation_counter_helper,
     instrumentation_stmt_counter_helper, instrumentation_visitor,
 };
 
@@ -32,14 +32,13 @@ impl<C: Clone + Comments, S: SourceMapper> VisitMut for SwitchCaseVisitor<C, S>
             Some(crate::hint_comments::IgnoreScope::Next) => {}
             _ => {
                 // TODO: conslidate brach expr creation, i.e ifstmt
-                let range =
-                    crate::lookup_range::get_range_from_span(&self.source_map, &switch_case.span);
+                let range = self.lookup_range(&switch_case.span);
                 let idx = self.cov.borrow_mut().add_branch_path(self.branch, &range);
-                let expr = crate::create_increase_counter_expr(
-                    &IDENT_B,
-                    self.branch,
+                let expr = self.counter_codegen.branch_increment(
                     &self.cov_fn_ident,
-                    Some(idx),
+                    &self.counter_idents.b,
+                    self.branch,
+                    idx,
                 );
 
                 switch_case.visit_mut_children_with(self);
@@ -0,0 +1,3 @@
+class Settings {
+  mode = args[0] ? 'strict' : 'loose';
+}
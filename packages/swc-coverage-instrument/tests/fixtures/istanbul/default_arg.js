@@ -0,0 +1,3 @@
+function greet(name = 'world') {
+  return 'hello ' + name;
+}
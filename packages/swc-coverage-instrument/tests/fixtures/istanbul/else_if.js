@@ -0,0 +1,9 @@
+function grade(score) {
+  if (score > 90) {
+    return 'A';
+  } else if (score > 50) {
+    return 'B';
+  } else {
+    return 'C';
+  }
+}
@@ -0,0 +1,6 @@
+var x = args[0];
+if (x > 1) {
+  x = 2;
+} else {
+  x = 3;
+}
@@ -0,0 +1,6 @@
+function check(value) {
+  if (value) {
+    return 1;
+  }
+  return 0;
+}
@@ -0,0 +1,2 @@
+var a = args[0], b = args[1];
+var value = a && b || 'fallback';
@@ -0,0 +1,2 @@
+var options = args[0];
+var timeout = options.timeout ?? 1000;
@@ -0,0 +1,8 @@
+var y = args[0];
+switch (y) {
+  case 1:
+    y = 10;
+    break;
+  default:
+    y = 0;
+}
@@ -0,0 +1,2 @@
+var flag = args[0];
+var result = flag ? 'yes' : 'no';
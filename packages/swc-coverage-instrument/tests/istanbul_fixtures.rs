@@ -0,0 +1,62 @@
+//! Compares coverage maps against the ones istanbul-lib-instrument creates for the same code.
+//!
+//! Each `fixtures/istanbul/<name>.js` has a golden `<name>.json` next to it, recorded from
+//! istanbul-lib-instrument's `instrumentSync` + `lastFileCoverage()` and trimmed down to
+//! `statementMap`, `fnMap` and `branchMap`. To add a case, record the golden for a new snippet
+//! the same way and drop both files into the directory.
+use std::{fs, path::Path};
+
+use pretty_assertions::assert_eq;
+use swc_coverage_instrument::{instrument_sync, InstrumentOptions};
+
+const FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/istanbul");
+
+fn read_fixtures() -> Vec<(String, String, serde_json::Value)> {
+    let mut fixtures: Vec<_> = fs::read_dir(FIXTURE_DIR)
+        .expect("Should able to read fixture directory")
+        .map(|entry| entry.expect("Should able to read fixture").path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "js"))
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            let code = fs::read_to_string(&path).expect("Should able to read fixture code");
+            let golden = fs::read_to_string(Path::new(FIXTURE_DIR).join(format!("{}.json", name)))
+                .unwrap_or_else(|_| panic!("Should have golden file for {}", name));
+
+            (
+                name,
+                code,
+                serde_json::from_str(&golden).expect("Should able to parse golden file"),
+            )
+        })
+        .collect();
+
+    fixtures.sort_by(|a, b| a.0.cmp(&b.0));
+    fixtures
+}
+
+#[test]
+fn should_match_istanbul_coverage_maps() {
+    let fixtures = read_fixtures();
+    assert!(!fixtures.is_empty(), "Should have fixtures to compare");
+
+    for (name, code, golden) in fixtures {
+        let result = instrument_sync(&code, &format!("{}.js", name), InstrumentOptions::default())
+            .unwrap_or_else(|err| panic!("Should able to instrument {}: {}", name, err));
+        let file_coverage = serde_json::to_value(&result.file_coverage).unwrap();
+
+        for map in ["statementMap", "fnMap", "branchMap"] {
+            assert_eq!(
+                file_coverage[map].as_object().map(|map| map.len()),
+                golden[map].as_object().map(|map| map.len()),
+                "{}: {} count differs from istanbul",
+                name,
+                map
+            );
+            assert_eq!(
+                file_coverage[map], golden[map],
+                "{}: {} differs from istanbul",
+                name, map
+            );
+        }
+    }
+}
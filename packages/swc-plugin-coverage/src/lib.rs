@@ -6,7 +6,8 @@ use swc_core::{
     },
 };
 use swc_coverage_instrument::{
-    create_coverage_instrumentation_visitor, InstrumentLogOptions, InstrumentOptions,
+    count_concatenated_sources, create_coverage_instrumentation_visitor, InstrumentLogOptions,
+    InstrumentOptions,
 };
 
 use tracing_subscriber::fmt::format::FmtSpan;
@@ -38,47 +39,123 @@ fn initialize_instrumentation_log(log_options: &InstrumentLogOptions) {
     }
 }
 
-#[plugin_transform]
-pub fn process(program: Program, metadata: TransformPluginProgramMetadata) -> Program {
-    let filename = metadata.get_context(&TransformPluginMetadataContextKind::Filename);
-    let filename = if let Some(filename) = filename.as_deref() {
-        filename
-    } else {
-        "unknown.js"
-    };
+/// Resolve filename from the transform context. Some hosts do not provide it or pass an empty string,
+/// falls back to `unknown.js` in those cases.
+fn resolve_filename(filename: Option<String>) -> String {
+    match filename {
+        Some(filename) if !filename.trim().is_empty() => filename,
+        _ => "unknown.js".to_string(),
+    }
+}
 
-    let plugin_config = metadata.get_transform_plugin_config();
-    let instrument_options: InstrumentOptions = if let Some(plugin_config) = plugin_config {
-        serde_json::from_str(&plugin_config).unwrap_or_else(|f| {
-            println!("Could not deserialize instrumentation option");
-            println!("{:#?}", f);
-            Default::default()
-        })
-    } else {
-        Default::default()
-    };
+/// Errors can occur while preparing the instrumentation. Plugin runs inside of the host's
+/// wasm runtime, panics abort the whole transform. Each error has a controlled fallback instead.
+#[derive(Debug)]
+enum PluginError {
+    /// Plugin config is not a valid `InstrumentOptions`. Falls back to the default options.
+    InvalidOptions(serde_json::Error),
+    /// `unstableExclude` is not a valid glob. Falls back to instrument the file.
+    InvalidExcludePattern(String),
+    /// Program consists of the multiple files concatenated by the host, delimited by `sourceMappingURL`
+    /// comments. Falls back to instrument them as a single file.
+    ConcatenatedSources { filename: String, sources: usize },
+}
 
-    // Unstable option to exclude files from coverage. If pattern is wax(https://crates.io/crates/wax)
-    // compatible glob and the filename matches the pattern, the file will not be instrumented.
-    // Note that the filename is provided by swc's core, may not be the full absolute path to the file name.
-    if let Some(exclude) = &instrument_options.unstable_exclude {
-        match wax::any(exclude.iter().map(|s| s.as_ref()).collect::<Vec<&str>>()) {
-            Ok(p) => {
-                if p.is_match(filename) {
-                    return program;
-                }
+impl std::fmt::Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginError::InvalidOptions(err) => {
+                write!(f, "Could not deserialize instrumentation option: {}", err)
+            }
+            PluginError::InvalidExcludePattern(err) => {
+                write!(
+                    f,
+                    "Could not parse unstable_exclude option, will be ignored: {}",
+                    err
+                )
             }
-            Err(e) => {
-                println!("Could not parse unstable_exclude option, will be ignored");
-                println!("{:#?}", e);
+            PluginError::ConcatenatedSources { filename, sources } => {
+                write!(
+                    f,
+                    "{} contains {} sources concatenated by sourceMappingURL comments, coverage is reported as a single file",
+                    filename, sources
+                )
             }
         }
     }
+}
+
+/// Report the error to the host. Plugin does not have a diagnostic channel, prints to stdout.
+fn report_error(err: &PluginError) {
+    println!("{}", err);
+}
+
+fn try_parse_instrument_options(
+    plugin_config: Option<String>,
+) -> Result<InstrumentOptions, PluginError> {
+    match plugin_config.as_deref().map(str::trim) {
+        Some(plugin_config) if !plugin_config.is_empty() => {
+            serde_json::from_str(plugin_config).map_err(PluginError::InvalidOptions)
+        }
+        _ => Ok(Default::default()),
+    }
+}
+
+/// Parse plugin config into instrumentation options. Falls back to default options
+/// if the config is empty or not able to deserialize instead of failing the whole transform.
+fn parse_instrument_options(plugin_config: Option<String>) -> InstrumentOptions {
+    try_parse_instrument_options(plugin_config).unwrap_or_else(|err| {
+        report_error(&err);
+        Default::default()
+    })
+}
+
+/// Unstable option to exclude files from coverage. If pattern is wax(https://crates.io/crates/wax)
+/// compatible glob and the filename matches the pattern, the file will not be instrumented.
+/// Note that the filename is provided by swc's core, may not be the full absolute path to the file name.
+fn try_is_excluded(filename: &str, exclude: Option<&[String]>) -> Result<bool, PluginError> {
+    match exclude {
+        Some(exclude) => wax::any(exclude.iter().map(|s| s.as_ref()).collect::<Vec<&str>>())
+            .map(|p| p.is_match(filename))
+            .map_err(|err| PluginError::InvalidExcludePattern(err.to_string())),
+        None => Ok(false),
+    }
+}
+
+/// Determine if the file should be excluded from the instrumentation. Invalid patterns are
+/// ignored, the file is instrumented as if there's no exclude option.
+fn is_excluded(filename: &str, exclude: Option<&[String]>) -> bool {
+    try_is_excluded(filename, exclude).unwrap_or_else(|err| {
+        report_error(&err);
+        false
+    })
+}
+
+#[plugin_transform]
+pub fn process(program: Program, metadata: TransformPluginProgramMetadata) -> Program {
+    let filename =
+        resolve_filename(metadata.get_context(&TransformPluginMetadataContextKind::Filename));
+    let filename = filename.as_str();
+
+    let instrument_options = parse_instrument_options(metadata.get_transform_plugin_config());
+
+    if is_excluded(filename, instrument_options.unstable_exclude.as_deref()) {
+        return program;
+    }
 
     initialize_instrumentation_log(&instrument_options.instrument_log);
 
+    let source_map = std::sync::Arc::new(metadata.source_map);
+    let sources = count_concatenated_sources(&source_map, metadata.comments.as_ref(), &program);
+    if sources > 1 {
+        report_error(&PluginError::ConcatenatedSources {
+            filename: filename.to_string(),
+            sources,
+        });
+    }
+
     let visitor = create_coverage_instrumentation_visitor(
-        std::sync::Arc::new(metadata.source_map),
+        source_map,
         metadata.comments.as_ref(),
         instrument_options,
         filename.to_string(),
@@ -86,3 +163,70 @@ pub fn process(program: Program, metadata: TransformPluginProgramMetadata) -> Pr
 
     program.apply(&mut visit_mut_pass(visitor))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_fallback_to_unknown_filename() {
+        assert_eq!(resolve_filename(None), "unknown.js");
+        assert_eq!(resolve_filename(Some("".to_string())), "unknown.js");
+        assert_eq!(resolve_filename(Some("src/a.js".to_string())), "src/a.js");
+    }
+
+    #[test]
+    fn should_fallback_to_default_options() {
+        assert_eq!(parse_instrument_options(None), InstrumentOptions::default());
+        assert_eq!(
+            parse_instrument_options(Some("".to_string())),
+            InstrumentOptions::default()
+        );
+        assert_eq!(
+            parse_instrument_options(Some("{ malformed".to_string())),
+            InstrumentOptions::default()
+        );
+    }
+
+    #[test]
+    fn should_return_error_for_malformed_options() {
+        assert!(matches!(
+            try_parse_instrument_options(Some("{ malformed".to_string())),
+            Err(PluginError::InvalidOptions(_))
+        ));
+        assert!(matches!(
+            try_parse_instrument_options(Some(r#"{ "reportLogic": "yes" }"#.to_string())),
+            Err(PluginError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn should_exclude_matching_files() {
+        let exclude = ["somepath/**/excluded.*".to_string()];
+        assert!(is_excluded("somepath/file/excluded.js", Some(&exclude[..])));
+        assert!(!is_excluded(
+            "somepath/file/included.js",
+            Some(&exclude[..])
+        ));
+        assert!(!is_excluded("somepath/file/excluded.js", None));
+    }
+
+    #[test]
+    fn should_fallback_to_instrument_with_invalid_exclude_pattern() {
+        let exclude = ["somepath/**/[".to_string()];
+        assert!(matches!(
+            try_is_excluded("somepath/file/excluded.js", Some(&exclude[..])),
+            Err(PluginError::InvalidExcludePattern(_))
+        ));
+        assert!(!is_excluded(
+            "somepath/file/excluded.js",
+            Some(&exclude[..])
+        ));
+    }
+
+    #[test]
+    fn should_parse_options() {
+        let options = parse_instrument_options(Some(r#"{ "reportLogic": true }"#.to_string()));
+        assert!(options.report_logic);
+    }
+}
@@ -1,4 +1,4 @@
 const COVERAGE_MAGIC_KEY = "_coverageSchema";
-const COVERAGE_MAGIC_VALUE = "7101652470475984838";
+const COVERAGE_MAGIC_VALUE = "1a1c01bbd47fc00a2c39e90264f33305004495a9";
 
 export { COVERAGE_MAGIC_KEY, COVERAGE_MAGIC_VALUE };
@@ -1,6 +1,6 @@
 import { assert } from "chai";
 import { getCoverageMagicConstants } from "./swc-coverage-instrument-wasm/pkg/swc_coverage_instrument_wasm";
-import { instrumentSync } from "./util/verifier";
+import { instrumentSync, lastFileCoverage } from "./util/verifier";
 
 // dummy: initiate wasm compilation before any test runs
 getCoverageMagicConstants();
@@ -29,3 +29,429 @@ ${code}
     );
   });
 });
+
+describe("Instrumentation options", () => {
+  it("should skip minified code", () => {
+    const code = `var a=1,b=2;function c(d){return d?a:b}console.log(c(a),c(b));`;
+
+    const output = instrumentSync(code, "minified.js", undefined, {
+      skipIfMinified: true,
+      minifiedLineLengthThreshold: 40,
+    });
+
+    assert.notInclude(output.code, "cov_");
+  });
+
+  it("should instrument non-minified code with skipIfMinified", () => {
+    const code = `var a = 1;
+function c(d) {
+  return d ? a : 2;
+}
+console.log(c(a));`;
+
+    const output = instrumentSync(code, "not-minified.js", undefined, {
+      skipIfMinified: true,
+      minifiedLineLengthThreshold: 40,
+    });
+
+    assert.include(output.code, "cov_");
+  });
+
+  it("should use custom counter idents", () => {
+    const code = `var x = args[0] ? 1 : 2;
+function foo() {}`;
+
+    const output = instrumentSync(code, "counter-idents.js", undefined, {
+      counterIdents: {
+        statement: "st",
+        function: "fn",
+        branch: "br",
+      },
+    });
+
+    // counter expressions
+    assert.match(output.code, /\.st\[0\]\+\+/);
+    assert.match(output.code, /\.fn\[0\]\+\+/);
+    assert.match(output.code, /\.br\[0\]\[0\]\+\+/);
+
+    // coverage metadata object
+    assert.match(output.code, /\bst: \{/);
+    assert.match(output.code, /\bfn: \{/);
+    assert.match(output.code, /\bbr: \{/);
+    assert.notMatch(output.code, /\.s\[0\]\+\+/);
+  });
+
+  it("should register coverage data into the global coverage variable", () => {
+    const code = `var x = 1;`;
+
+    const output = instrumentSync(code, "global-registration.js", undefined, {
+      coverageVariable: "__custom_coverage__",
+    });
+
+    assert.include(output.code, `var gcv = "__custom_coverage__"`);
+    assert.match(
+      output.code,
+      /var coverage = global\[gcv\] \|\| \(global\[gcv\] = \{\}\)/,
+    );
+    assert.match(output.code, /coverage\[path\] = coverageData/);
+  });
+
+  it("should resolve the global scope with a fallback chain", () => {
+    const code = `var x = 1;`;
+
+    const output = instrumentSync(code, "global-fallback.js");
+
+    assert.match(
+      output.code,
+      /var global = typeof globalThis !== "undefined" \? globalThis : typeof self !== "undefined" \? self : typeof window !== "undefined" \? window : new \(\(function\(\)\{\}\)\.constructor\)\("return this"\)\(\)/,
+    );
+  });
+
+  it("should evaluate the given coverage global scope", () => {
+    const code = `var x = 1;`;
+
+    const output = instrumentSync(code, "global-scope.js", undefined, {
+      coverageGlobalScope: "globalThis",
+    });
+
+    assert.match(
+      output.code,
+      /var global = new \(\(function\(\)\{\}\)\.constructor\)\("return globalThis"\)\(\)/,
+    );
+  });
+
+  it("should namespace the global coverage variable", () => {
+    const code = `var x = 1;`;
+
+    const output = instrumentSync(code, "namespaced.js", undefined, {
+      coverageVariable: "__custom_coverage__",
+      coverageVariableNamespace: "build42",
+    });
+
+    assert.include(output.code, `var gcv = "__custom_coverage___build42"`);
+  });
+
+  it("should count functions with typed array counters", () => {
+    const code = `function foo() {
+  return 1;
+}
+for (var i = 0; i < 1000; i++) foo();`;
+
+    const run = (coverageVariable: string, typedArrayFnCounters: boolean) => {
+      const output = instrumentSync(
+        code,
+        "typed-array-fn-counters.js",
+        undefined,
+        { coverageVariable, typedArrayFnCounters },
+      );
+      new Function(output.code)();
+
+      const coverage = new Function("return this")()[coverageVariable];
+      return { code: output.code, f: Object.values(coverage)[0].f };
+    };
+
+    const typed = run("__typed_array_fn_coverage__", true);
+    const plain = run("__plain_fn_coverage__", false);
+
+    assert.include(typed.code, "new Uint32Array(1)");
+    assert.match(typed.code, /\.f\[0\] \+= 1/);
+    assert.deepEqual(Array.from(typed.f), [1000]);
+    assert.deepEqual(Object.values(plain.f), [1000]);
+  });
+
+  it("should emit line hits consistent with statement hits", () => {
+    const code = `var x = 0;
+for (var i = 0; i < 3; i++) { x++; x++; }
+if (x > 100) { x = 0; }`;
+
+    const run = (coverageVariable: string, trackLines: boolean) => {
+      const output = instrumentSync(code, "track-lines.js", undefined, {
+        coverageVariable,
+        trackLines,
+      });
+      new Function(output.code)();
+
+      const coverage = new Function("return this")()[coverageVariable];
+      return JSON.parse(JSON.stringify(Object.values(coverage)[0]));
+    };
+
+    const tracked = run("__track_lines_coverage__", true);
+    assert.deepEqual(tracked.l, { "1": 1, "2": 3, "3": 1 });
+
+    // Same as the line coverage reporters derive from the statement hits.
+    const derived: Record<string, number> = {};
+    Object.entries(tracked.statementMap).forEach(([id, loc]: [string, any]) => {
+      const line = loc.start.line;
+      if (!(line in derived) || derived[line] < tracked.s[id]) {
+        derived[line] = tracked.s[id];
+      }
+    });
+    assert.deepEqual(tracked.l, derived);
+
+    assert.notProperty(run("__untracked_lines_coverage__", false), "l");
+  });
+
+  it("should keep counters intact after lowering using declarations", () => {
+    const code = `function foo(a) {
+  using res = a ? null : undefined;
+  return 1;
+}
+foo(true);`;
+
+    const output = instrumentSync(
+      code,
+      "using-lowering.js",
+      undefined,
+      { coverageVariable: "__using_lowering_coverage__" },
+      {
+        jsc: {
+          parser: { syntax: "ecmascript", explicitResourceManagement: true },
+        },
+      },
+    );
+    assert.notMatch(output.code, /\busing res\b/);
+
+    new Function(output.code)();
+
+    const coverage = Object.values(
+      new Function("return this")()["__using_lowering_coverage__"],
+    )[0] as any;
+    assert.deepEqual(coverage.s, { "0": 1, "1": 1, "2": 1 });
+    assert.deepEqual(coverage.b, { "0": [1, 0] });
+    assert.deepEqual(coverage.f, { "0": 1 });
+  });
+
+  it("should leave pre-instrumented code unchanged", () => {
+    const code = `var x = 1;`;
+    const covFnDecl = /function cov_\d+\(\)/g;
+
+    const instrumented = instrumentSync(code, "pre-instrumented.js").code;
+    const detected = instrumentSync(
+      instrumented,
+      "pre-instrumented.js",
+      undefined,
+      { detectPreInstrumented: true },
+    );
+    const undetected = instrumentSync(instrumented, "pre-instrumented.js");
+
+    assert.lengthOf(detected.code.match(covFnDecl), 1);
+    assert.lengthOf(undetected.code.match(covFnDecl), 2);
+  });
+
+  it("should batch counters of straight-line statements with same counts if none throws", () => {
+    const code = `var a = 0, b = 0;
+for (var i = 0; i < 10; i++) {
+  a += i;
+  b = a * 2;
+  a = b - i;
+  if (a > 50) {
+    break;
+  }
+}`;
+
+    const run = (coverageVariable: string, optimize: boolean) => {
+      const output = instrumentSync(code, "straight-line.js", undefined, {
+        coverageVariable,
+        optimizeStraightLineBlocks: optimize,
+      });
+      new Function(output.code)();
+
+      const coverage = new Function("return this")()[coverageVariable];
+      return {
+        code: output.code,
+        coverage: Object.values(coverage)[0] as any,
+      };
+    };
+
+    const optimized = run("__optimized_straight_line_coverage__", true);
+    const plain = run("__plain_straight_line_coverage__", false);
+
+    assert.match(
+      optimized.code,
+      /\.s\[\d+\]\+\+, cov_\d+\(\)\.s\[\d+\]\+\+/,
+    );
+    assert.deepEqual(optimized.coverage.s, plain.coverage.s);
+    assert.deepEqual(optimized.coverage.b, plain.coverage.b);
+  });
+
+  it("should coalesce adjacent counters with same counts", () => {
+    const code = `var a = function () { return 1; }, b = function () { return 2; };
+function foo() {
+  var c = function () {}, d = () => {};
+  return a() + b();
+}
+output = foo();`;
+
+    const run = (coverageVariable: string, coalesceCounters: boolean) => {
+      const output = instrumentSync(code, "coalesce-counters.js", undefined, {
+        coverageVariable,
+        coalesceCounters,
+      });
+      new Function(output.code)();
+
+      const coverage = new Function("return this")()[coverageVariable];
+      return {
+        code: output.code,
+        coverage: Object.values(coverage)[0] as any,
+      };
+    };
+
+    const coalesced = run("__coalesced_counters_coverage__", true);
+    const plain = run("__plain_counters_coverage__", false);
+
+    assert.match(
+      coalesced.code,
+      /\.s\[\d+\]\+\+, cov_\d+\(\)\.s\[\d+\]\+\+/,
+    );
+    assert.isBelow(
+      coalesced.code.split(";").length,
+      plain.code.split(";").length,
+    );
+    assert.deepEqual(coalesced.coverage.s, plain.coverage.s);
+    assert.deepEqual(coalesced.coverage.f, plain.coverage.f);
+    assert.deepEqual(coalesced.coverage.b, plain.coverage.b);
+  });
+
+  it("should explicitly initialize coverage by default", () => {
+    const code = `var x = 1;`;
+
+    const output = instrumentSync(code, "initialize-default.js");
+
+    assert.match(output.code, /^\s*cov_\d+\(\);$/m);
+  });
+
+  it("should omit coverage initialization call with lazy mode", () => {
+    const code = `var x = 1;`;
+
+    const output = instrumentSync(code, "initialize-lazy.js", undefined, {
+      initializeCoverage: "lazy",
+    });
+
+    assert.notMatch(output.code, /^\s*cov_\d+\(\);$/m);
+    assert.match(output.code, /function cov_\d+\(\)/);
+    assert.include(output.code, "statementMap");
+  });
+
+  it("should emit summary of instrumented counters", () => {
+    const code = `function foo(a) {
+  return a ? 1 : 2;
+}
+foo(1);`;
+
+    const output = instrumentSync(code, "emit-summary.js", undefined, {
+      emitSummary: true,
+    });
+
+    assert.include(
+      output.code,
+      `__coverage_summary__::{"statements":2,"branches":2,"functions":1}`,
+    );
+  });
+
+  it("should emit flush hook only with flushOnExit", () => {
+    const code = `var x = 1;`;
+
+    const withFlush = instrumentSync(code, "flush-on-exit.js", undefined, {
+      flushOnExit: true,
+    });
+    const withoutFlush = instrumentSync(code, "no-flush-on-exit.js");
+
+    assert.include(withFlush.code, `process.once("exit"`);
+    assert.notInclude(withoutFlush.code, `process.once("exit"`);
+  });
+
+  it("should flush actual coverage via a single shared exit hook", () => {
+    const coverageVariable = "__flush_on_exit_coverage__";
+    const globalScope = new Function("return this")();
+    const listenerCount = process.listenerCount("exit");
+
+    ["flush-a.js", "flush-b.js", "flush-c.js"].forEach((filename) => {
+      const output = instrumentSync(`var x = 1;`, filename, undefined, {
+        coverageVariable,
+        flushOnExit: true,
+      });
+      new Function(output.code)();
+    });
+
+    assert.equal(process.listenerCount("exit"), listenerCount + 1);
+    assert.equal(Object.keys(globalScope[coverageVariable]).length, 3);
+
+    // Global coverage variable is reset before the reporter reads it.
+    const coverage = globalScope[coverageVariable];
+    globalScope[coverageVariable] = {};
+
+    const exitHook = process.listeners("exit").slice(-1)[0];
+    process.removeListener("exit", exitHook);
+    exitHook(0);
+
+    assert.equal(Object.keys(globalScope[coverageVariable]).length, 3);
+    Object.keys(coverage).forEach((path) => {
+      assert.strictEqual(globalScope[coverageVariable][path], coverage[path]);
+      assert.deepEqual(globalScope[coverageVariable][path].s, { "0": 1 });
+    });
+  });
+
+  it("should escape comment terminator in embedded coverage data", () => {
+    const code = `var x = "*/";`;
+
+    const output = instrumentSync(code, "comment-terminator.js", {
+      version: 3,
+      file: "comment-terminator.js",
+      sources: ["comment-terminator.js"],
+      sourcesContent: [code],
+      names: [],
+      mappings: "",
+    });
+
+    const coverage = lastFileCoverage(output.code);
+    assert.equal(coverage.inputSourceMap.sourcesContent[0], code);
+  });
+
+  it("should use custom coverage comment prefix", () => {
+    const code = `var x = 1;`;
+
+    const output = instrumentSync(code, "comment-prefix.js", undefined, {
+      coverageCommentPrefix: "__custom_coverage_comment__::",
+    });
+
+    assert.notInclude(output.code, "__coverage_data_json_comment__::");
+    assert.ok(
+      lastFileCoverage(output.code, "__custom_coverage_comment__::").statementMap,
+    );
+  });
+
+  it("should decode msgpack-base64 encoded coverage data", () => {
+    const code = `function foo(a) {
+  return a ? 1 : 2;
+}
+var x = foo(args[0]);`;
+
+    const jsonOutput = instrumentSync(code, "metadata-encoding.js");
+    const msgpackOutput = instrumentSync(
+      code,
+      "metadata-encoding.js",
+      undefined,
+      { metadataEncoding: "msgpack-base64" },
+    );
+
+    assert.notInclude(msgpackOutput.code, "__coverage_data_json_comment__::{");
+    assert.deepEqual(
+      lastFileCoverage(msgpackOutput.code),
+      lastFileCoverage(jsonOutput.code),
+    );
+  });
+});
+
+describe("Instrumented output", () => {
+  it("should preserve numeric literal formatting in wrapped expressions", () => {
+    const code = `var flags = args[0];
+var x = (0x1F_00 | flags) || 1_000_000 && 0b1010_0101;
+output = x;`;
+
+    const output = instrumentSync(code, "numeric-literals.js");
+
+    assert.include(output.code, "0x1F_00 | flags");
+    assert.include(output.code, "1_000_000");
+    assert.include(output.code, "0b1010_0101");
+  });
+});
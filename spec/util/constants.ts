@@ -1,5 +1,5 @@
 const COVERAGE_MAGIC_KEY = "_coverageSchema";
-const COVERAGE_MAGIC_VALUE = "11020577277169172593";
+const COVERAGE_MAGIC_VALUE = "1a1c01bbd47fc00a2c39e90264f33305004495a9";
 
 export {
   COVERAGE_MAGIC_KEY,
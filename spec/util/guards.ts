@@ -24,6 +24,10 @@ function isClassPrivatePropAvailable() {
   return tryThis("class Foo { #a = 1; }", "class private property");
 }
 
+function isClassStaticBlockAvailable() {
+  return tryThis("class Foo { static a = 1; static { Foo.a++; } }", "class static block");
+}
+
 function isForOfAvailable() {
   return tryThis(
     "function *foo() { yield 1; }\n" + "for (var k of foo()) {}",
@@ -31,6 +35,14 @@ function isForOfAvailable() {
   );
 }
 
+function isForAwaitOfAvailable() {
+  return tryThis(
+    "async function *foo() { yield 1; }\n" +
+      "async function bar() { for await (var k of foo()) {} }",
+    "for-await-of"
+  );
+}
+
 function isArrowFnAvailable() {
   return tryThis("[1 ,2, 3].map(x => x * x)", "arrow function");
 }
@@ -39,6 +51,10 @@ function isObjectSpreadAvailable() {
   return tryThis("const a = {...{b: 33}}", "object-spread");
 }
 
+function isLogicalAssignmentAvailable() {
+  return tryThis("var a = 1; a &&= 2; a ||= 3; a ??= 4;", "logical assignment");
+}
+
 function isObjectFreezeAvailable() {
   if (!Object.freeze) {
     return false;
@@ -60,6 +76,10 @@ function isImportAvailable() {
   return tryThis('import fs from "fs"', "import", true);
 }
 
+function isDynamicImportAvailable() {
+  return tryThis("(function () { return import('x'); })", "dynamic import");
+}
+
 function isExportAvailable() {
   return tryThis("export default function foo() {}", "export", true);
 }
@@ -91,12 +111,16 @@ export {
   isDefaultArgsAvailable,
   isExportAvailable,
   isImportAvailable,
+  isDynamicImportAvailable,
   isOptionalCatchBindingAvailable,
   isObjectFreezeAvailable,
   isYieldAvailable,
   isClassPropAvailable,
   isClassPrivatePropAvailable,
+  isClassStaticBlockAvailable,
   isForOfAvailable,
+  isForAwaitOfAvailable,
   isArrowFnAvailable,
   isObjectSpreadAvailable,
+  isLogicalAssignmentAvailable,
 };
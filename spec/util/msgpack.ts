@@ -0,0 +1,140 @@
+/**
+ * Minimal MessagePack decoder for the `msgpack-base64` coverage data payload.
+ * Covers the types `rmp_serde` emits for the coverage data (nil, bool, int, float, str, bin, array, map),
+ * map keys are converted into object keys the same as JSON.
+ */
+const decodeMsgpack = (bytes: Uint8Array): any => {
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  const decoder = new TextDecoder();
+  let offset = 0;
+
+  const readStr = (length: number) => {
+    const value = decoder.decode(bytes.subarray(offset, offset + length));
+    offset += length;
+    return value;
+  };
+
+  const readBin = (length: number) => {
+    const value = bytes.slice(offset, offset + length);
+    offset += length;
+    return value;
+  };
+
+  const readArray = (length: number) => {
+    const value: Array<any> = [];
+    for (let i = 0; i < length; i++) {
+      value.push(read());
+    }
+    return value;
+  };
+
+  const readMap = (length: number) => {
+    const value: Record<string, any> = {};
+    for (let i = 0; i < length; i++) {
+      const key = read();
+      value[String(key)] = read();
+    }
+    return value;
+  };
+
+  const read = (): any => {
+    const type = view.getUint8(offset++);
+
+    // positive fixint, fixmap, fixarray, fixstr, negative fixint
+    if (type <= 0x7f) return type;
+    if (type <= 0x8f) return readMap(type & 0x0f);
+    if (type <= 0x9f) return readArray(type & 0x0f);
+    if (type <= 0xbf) return readStr(type & 0x1f);
+    if (type >= 0xe0) return type - 0x100;
+
+    let value: any;
+    switch (type) {
+      case 0xc0:
+        return null;
+      case 0xc2:
+        return false;
+      case 0xc3:
+        return true;
+      case 0xc4:
+        return readBin(view.getUint8(offset++));
+      case 0xc5:
+        value = view.getUint16(offset);
+        offset += 2;
+        return readBin(value);
+      case 0xc6:
+        value = view.getUint32(offset);
+        offset += 4;
+        return readBin(value);
+      case 0xca:
+        value = view.getFloat32(offset);
+        offset += 4;
+        return value;
+      case 0xcb:
+        value = view.getFloat64(offset);
+        offset += 8;
+        return value;
+      case 0xcc:
+        return view.getUint8(offset++);
+      case 0xcd:
+        value = view.getUint16(offset);
+        offset += 2;
+        return value;
+      case 0xce:
+        value = view.getUint32(offset);
+        offset += 4;
+        return value;
+      case 0xcf:
+        value = Number(view.getBigUint64(offset));
+        offset += 8;
+        return value;
+      case 0xd0:
+        return view.getInt8(offset++);
+      case 0xd1:
+        value = view.getInt16(offset);
+        offset += 2;
+        return value;
+      case 0xd2:
+        value = view.getInt32(offset);
+        offset += 4;
+        return value;
+      case 0xd3:
+        value = Number(view.getBigInt64(offset));
+        offset += 8;
+        return value;
+      case 0xd9:
+        return readStr(view.getUint8(offset++));
+      case 0xda:
+        value = view.getUint16(offset);
+        offset += 2;
+        return readStr(value);
+      case 0xdb:
+        value = view.getUint32(offset);
+        offset += 4;
+        return readStr(value);
+      case 0xdc:
+        value = view.getUint16(offset);
+        offset += 2;
+        return readArray(value);
+      case 0xdd:
+        value = view.getUint32(offset);
+        offset += 4;
+        return readArray(value);
+      case 0xde:
+        value = view.getUint16(offset);
+        offset += 2;
+        return readMap(value);
+      case 0xdf:
+        value = view.getUint32(offset);
+        offset += 4;
+        return readMap(value);
+      default:
+        throw new Error(
+          `Unsupported msgpack type 0x${type.toString(16)} at ${offset - 1}`,
+        );
+    }
+  };
+
+  return read();
+};
+
+export { decodeMsgpack };
@@ -2,6 +2,7 @@ import { Options, transformSync } from "@swc/core";
 import * as path from "path";
 import { assert } from "chai";
 import { readInitialCoverage } from "./read-coverage";
+import { decodeMsgpack } from "./msgpack";
 import { EOL } from "os";
 import { FileCoverageInterop } from "../swc-coverage-instrument-wasm/pkg/swc_coverage_instrument_wasm";
 
@@ -33,6 +34,7 @@ const instrumentSync = (
       parser: {
         syntax: "ecmascript",
         jsx: true,
+        ...(transformOptions?.jsc?.parser ?? {}),
       },
       target: "es2022",
       preserveAllComments: true,
@@ -79,14 +81,22 @@ const instrumentSync = (
  * SWC's plugin transform does not allow to pass arbiatary data other than transformed AST, using trailing comment
  * to grab out data from plugin.
  */
-const lastFileCoverage = (code?: string) => {
+const lastFileCoverage = (
+  code?: string,
+  prefix: string = "__coverage_data_json_comment__::",
+) => {
   const lines = (code ?? "").split(EOL);
-  const commentLine = lines
-    .find((v) => v.includes("__coverage_data_json_comment__::"))
-    ?.split("__coverage_data_json_comment__::")[1];
+  const commentLine = lines.find((v) => v.includes(prefix))?.split(prefix)[1];
 
   const data = commentLine?.substring(0, commentLine.indexOf("*/"));
-  return data ? JSON.parse(data) : {};
+  if (!data) {
+    return {};
+  }
+
+  // JSON payload always starts with `{`, otherwise it is `msgpack-base64` encoded.
+  return data.startsWith("{")
+    ? JSON.parse(data)
+    : decodeMsgpack(Buffer.from(data, "base64"));
 };
 
 type UnknownReserved = any;
@@ -300,9 +310,12 @@ const create = (code, options = {}, instrumentOptions = {}, inputSourceMap) => {
     generatedCode: instrumenterOutput,
     coverageVariable,
     baseline: clone(g[coverageVariable]),
-    emptyCoverage: lastFileCoverage(instrumenterOutput), //instrumenter.getLastFileCoverage()
+    emptyCoverage: lastFileCoverage(
+      instrumenterOutput,
+      instrumentOptions.coverageCommentPrefix,
+    ), //instrumenter.getLastFileCoverage()
     transformOptions: options.transformOptions,
   });
 };
 
-export { create, instrumentSync };
+export { create, instrumentSync, lastFileCoverage };